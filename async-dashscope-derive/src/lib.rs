@@ -0,0 +1,371 @@
+//! `async-dashscope` 的派生宏
+//!
+//! 请通过 `async-dashscope` 的 `derive` feature 使用，参见 `async_dashscope::schema`。
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, Meta, Token,
+    meta::ParseNestedMeta, parse_macro_input, spanned::Spanned,
+};
+
+/// 为结构体或只包含单元变体的枚举生成 `ToolSchema` 实现
+///
+/// 支持 `#[serde(rename, rename_all, default, skip, skip_deserializing)]`，
+/// 字段上的文档注释会作为该字段的 `description`。
+#[proc_macro_derive(ToolSchema, attributes(tool))]
+pub fn derive_tool_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_tool_schema(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// 为具名字段的结构体生成 `DashScopeTool` 实现，需要同时实现 `ToolSchema` 和 `Deserialize`
+///
+/// 工具名默认为结构体名的 snake_case 形式，描述默认为结构体的文档注释，
+/// 可以通过 `#[tool(name = "...", description = "...")]` 覆盖。
+#[proc_macro_derive(DashScopeTool, attributes(tool))]
+pub fn derive_dashscope_tool(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_dashscope_tool(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+/// 跳过不关心的 serde 参数，例如 `skip_serializing_if = "..."` 或 `with(...)`
+fn skip_meta_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta_value(&nested))?;
+    }
+    Ok(())
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> syn::Result<SerdeAttrs> {
+    let mut result = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                if meta.input.peek(Token![=]) {
+                    result.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    // rename(deserialize = "...")
+                    meta.parse_nested_meta(|nested| {
+                        if nested.path.is_ident("deserialize") {
+                            result.rename = Some(nested.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            skip_meta_value(&nested)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+            } else if meta.path.is_ident("rename_all") {
+                if meta.input.peek(Token![=]) {
+                    result.rename_all = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    meta.parse_nested_meta(|nested| {
+                        if nested.path.is_ident("deserialize") {
+                            result.rename_all = Some(nested.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            skip_meta_value(&nested)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+            } else if meta.path.is_ident("default") {
+                result.default = true;
+                skip_meta_value(&meta)?;
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                result.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                result.flatten = true;
+            } else {
+                skip_meta_value(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+#[derive(Default)]
+struct ToolAttrs {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+fn tool_attrs(attrs: &[Attribute]) -> syn::Result<ToolAttrs> {
+    let mut result = ToolAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("tool")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                result.name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("description") {
+                result.description = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `name` or `description`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+/// 合并文档注释，每行去掉首尾空白
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn split_words(ident: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in ident.split('_').filter(|p| !p.is_empty()) {
+        let mut current = String::new();
+        for c in part.chars() {
+            if c.is_uppercase() && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+    }
+    words.into_iter().map(|w| w.to_lowercase()).collect()
+}
+
+/// 与 serde 的 `RenameRule` 保持一致：字段名按 snake_case、变体名按 PascalCase 转换
+fn apply_rename_all(
+    ident: &str,
+    rule: &str,
+    is_variant: bool,
+    span: proc_macro2::Span,
+) -> syn::Result<String> {
+    let renamed = if is_variant {
+        rename_variant(ident, rule)
+    } else {
+        rename_field(ident, rule)
+    };
+    renamed.ok_or_else(|| syn::Error::new(span, format!("unsupported rename_all rule: {rule}")))
+}
+
+fn rename_field(field: &str, rule: &str) -> Option<String> {
+    Some(match rule {
+        "lowercase" | "snake_case" => field.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" | "camelCase" => {
+            let mut pascal = String::new();
+            let mut capitalize = true;
+            for c in field.chars() {
+                if c == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    pascal.push(c.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    pascal.push(c);
+                }
+            }
+            if rule == "camelCase" {
+                lowercase_first(&pascal)
+            } else {
+                pascal
+            }
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
+
+fn rename_variant(variant: &str, rule: &str) -> Option<String> {
+    let snake = || {
+        let mut snake = String::new();
+        for (i, c) in variant.char_indices() {
+            if i > 0 && c.is_uppercase() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+        snake
+    };
+    Some(match rule {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "PascalCase" => variant.to_string(),
+        "camelCase" => lowercase_first(variant),
+        "snake_case" => snake(),
+        "SCREAMING_SNAKE_CASE" => snake().to_ascii_uppercase(),
+        "kebab-case" => snake().replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake().to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
+
+fn lowercase_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn option_tokens(value: Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    }
+}
+
+fn expand_tool_schema(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let container = serde_attrs(&input.attrs)?;
+    let description = option_tokens(
+        tool_attrs(&input.attrs)?
+            .description
+            .or_else(|| doc_comment(&input.attrs)),
+    );
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let mut calls = Vec::new();
+                for field in &fields.named {
+                    let attrs = serde_attrs(&field.attrs)?;
+                    if attrs.skip {
+                        continue;
+                    }
+                    if attrs.flatten {
+                        return Err(syn::Error::new(
+                            field.span(),
+                            "#[serde(flatten)] is not supported by ToolSchema",
+                        ));
+                    }
+                    let field_ident = field.ident.as_ref().expect("named field").to_string();
+                    let field_ident = field_ident.trim_start_matches("r#");
+                    let name = match (&attrs.rename, &container.rename_all) {
+                        (Some(rename), _) => rename.clone(),
+                        (None, Some(rule)) => {
+                            apply_rename_all(field_ident, rule, false, field.span())?
+                        }
+                        (None, None) => field_ident.to_string(),
+                    };
+                    let ty = &field.ty;
+                    let field_description = option_tokens(doc_comment(&field.attrs));
+                    let has_default = attrs.default || container.default;
+                    calls.push(quote! {
+                        .field::<#ty>(#name, #field_description, #has_default)
+                    });
+                }
+                quote! {
+                    ::async_dashscope::schema::ObjectSchema::new()
+                        .description(#description)
+                        #(#calls)*
+                        .build()
+                }
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed.first().expect("one field").ty;
+                quote!(<#ty as ::async_dashscope::schema::ToolSchema>::schema())
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "ToolSchema can only be derived for structs with named fields or newtypes",
+                ));
+            }
+        },
+        Data::Enum(data) => {
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        "ToolSchema can only be derived for enums with unit variants",
+                    ));
+                }
+                let attrs = serde_attrs(&variant.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let variant_ident = variant.ident.to_string();
+                variants.push(match (&attrs.rename, &container.rename_all) {
+                    (Some(rename), _) => rename.clone(),
+                    (None, Some(rule)) => {
+                        apply_rename_all(&variant_ident, rule, true, variant.span())?
+                    }
+                    (None, None) => variant_ident,
+                });
+            }
+            quote! {
+                ::async_dashscope::schema::enum_schema(&[#(#variants),*], #description)
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "ToolSchema cannot be derived for unions",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::async_dashscope::schema::ToolSchema for #ident #ty_generics #where_clause {
+            fn schema() -> ::async_dashscope::schema::Value {
+                #body
+            }
+        }
+    })
+}
+
+fn expand_dashscope_tool(input: DeriveInput) -> syn::Result<TokenStream2> {
+    // 工具参数必须是 JSON 对象，否则 `function_call` 无法生成参数定义
+    if !matches!(&input.data, Data::Struct(data) if matches!(data.fields, Fields::Named(_))) {
+        return Err(syn::Error::new(
+            input.span(),
+            "DashScopeTool can only be derived for structs with named fields",
+        ));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let attrs = tool_attrs(&input.attrs)?;
+    let name = attrs
+        .name
+        .unwrap_or_else(|| split_words(&ident.to_string()).join("_"));
+    let description = attrs
+        .description
+        .or_else(|| doc_comment(&input.attrs))
+        .unwrap_or_default();
+
+    Ok(quote! {
+        impl #impl_generics ::async_dashscope::schema::DashScopeTool for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const DESCRIPTION: &'static str = #description;
+        }
+    })
+}
@@ -0,0 +1,30 @@
+use async_dashscope::{operation::generation::ChatSession, Client};
+use futures_util::StreamExt;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv()?;
+    let client = Client::default();
+
+    let mut session = ChatSession::new("qwen-plus").with_system("你是一个简洁的助手。");
+
+    let output = session.send(&client, "我叫小明，请记住我的名字。").await?;
+    println!("{:?}", output.output.choices);
+
+    // 流式回复，结束后自动追加到历史中
+    {
+        let mut stream = session.send_stream(&client, "我叫什么名字？").await?;
+        while let Some(chunk) = stream.next().await {
+            for choice in chunk?.output.choices.iter().flatten() {
+                print!("{}", choice.message.content);
+            }
+        }
+        println!();
+    }
+
+    // 持久化并恢复对话
+    let json = session.to_json()?;
+    let restored = ChatSession::from_json(&json)?;
+    println!("restored {} messages", restored.messages().len());
+    Ok(())
+}
@@ -1,11 +1,11 @@
 use async_dashscope::{
     operation::{
+        accumulator::CollectFinal as _,
         common::ParametersBuilder,
         generation::{GenerationParamBuilder, InputBuilder, MessageBuilder},
     },
     Client,
 };
-use tokio_stream::StreamExt;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -32,13 +32,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let client = Client::default();
 
-    let mut stream = client.generation().call_stream(request).await?;
-    // dbg!(&response);
+    let stream = client.generation().call_stream(request).await?;
     // 思考过程
     println!("思考过程:::");
-    while let Some(response) = stream.next().await {
-        match response {
-            Ok(go) => go.output.choices.unwrap().iter().for_each(|c| {
+    let output = stream
+        .collect_final_with(|go| {
+            go.output.choices.iter().flatten().for_each(|c| {
                 if let Some(reasoning_content) = &c.message.reasoning_content
                     && !reasoning_content.is_empty()
                 {
@@ -46,9 +45,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 } else if !c.message.content.is_empty() {
                     print!("{content}", content = c.message.content);
                 }
-            }),
-            Err(e) => eprintln!("{e}"),
-        }
-    }
+            })
+        })
+        .await?;
+    println!();
+    println!("usage: {:?}", output.usage);
     Ok(())
 }
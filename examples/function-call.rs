@@ -1,37 +1,37 @@
 use async_dashscope::{
     operation::{
-        common::{FunctionBuilder, FunctionCallBuilder, ParametersBuilder},
+        common::ParametersBuilder,
         generation::{GenerationParamBuilder, InputBuilder, MessageBuilder},
+        tool::ToolRegistry,
     },
     Client,
 };
+use serde_json::json;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv()?;
 
-    let mut messages = vec![MessageBuilder::default()
+    let messages = vec![MessageBuilder::default()
         .role("user")
         .content("现在是什么时间？")
         .build()
         .unwrap()];
 
-    // add function call
+    // 注册工具，run_with_tools 会自动把工具定义加入请求参数
+    let mut tools = ToolRegistry::new();
+    tools.register(
+        "get_current_time",
+        "return the current time",
+        json!({"type": "object", "properties": {}}),
+        |_args| async { Ok::<_, std::io::Error>(get_current_time()) },
+    )?;
+
     let request = GenerationParamBuilder::default()
         .model("qwen-turbo".to_string())
-        .input(InputBuilder::default().messages(messages.clone()).build()?)
+        .input(InputBuilder::default().messages(messages).build()?)
         .parameters(
             ParametersBuilder::default()
-                .functions([FunctionCallBuilder::default()
-                    .typ("function")
-                    .function(
-                        FunctionBuilder::default()
-                            .name("get_current_time")
-                            .description("return the current time")
-                            .build()?,
-                    )
-                    .build()?])
-                // or call .tools(value)
                 .result_format("message")
                 .parallel_tool_calls(true)
                 .build()?,
@@ -40,40 +40,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let client = Client::default();
 
-    let response = client.generation().call(request).await?;
-    // dbg!(response);
-    let response_message = response.output.choices.unwrap().first().unwrap().message.clone();
-    // get  function call arguments
-    if let Some(func_calls) = response_message.tool_calls {
-        for call in &func_calls {
-            if call.function.name == "get_current_time" {
-                let func_response = get_current_time();
-                messages.push(
-                    MessageBuilder::default()
-                        .role("user")
-                        .content(func_response)
-                        .build()?,
-                );
-                break;
-            }
-        }
-
-        // 结合函数调用的结果,重新请求
-        let request = GenerationParamBuilder::default()
-            .model("qwen-turbo".to_string())
-            .input(InputBuilder::default().messages(messages.clone()).build()?)
-            .build()?;
-
-        let response = client.generation().call(request).await?;
+    // 执行工具调用并结合结果重新请求，直到得到最终回复
+    let result = client.generation().run_with_tools(request, &tools).await?;
 
-        // 返回最终总结结果
-        dbg!(&response.output.text);
-        
+    // 返回最终总结结果
+    if let Some(choices) = &result.output.output.choices {
+        dbg!(&choices[0].message.content);
     }
+    println!("iterations: {}", result.iterations);
     Ok(())
 }
 
-#[allow(dead_code)]
 fn get_current_time() -> String {
     "2025-06-05 16:00:00".to_string()
 }
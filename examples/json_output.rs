@@ -1,12 +1,16 @@
 use async_dashscope::{
-    operation::{
-        common::{ParametersBuilder, ResponseFormatBuilder},
-        generation::{GenerationParamBuilder, InputBuilder, MessageBuilder},
-    },
+    operation::generation::{GenerationParamBuilder, InputBuilder, MessageBuilder},
     Client,
 };
 use serde::Deserialize;
 
+#[derive(Deserialize, Debug, Clone)]
+struct Person {
+    pub name: String,
+    pub age: String,
+    pub email: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv()?;
@@ -22,37 +26,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .role("user").content("大家好，我叫刘五，今年34岁，邮箱是liuwu@example.com").build()?])
                 .build()?,
         )
-        .parameters(
-            ParametersBuilder::default()
-                .result_format("message")
-                .response_format(ResponseFormatBuilder::default().type_("json_object")
-            .build()?)
-                .build()?,
-        )
         .build()?;
 
     let client = Client::default();
 
-    let response = client.generation().call(request).await?;
-    if let Some(choices) =  response.output.choices {
-        
-        for choice in choices {
-
-            #[derive(Deserialize,Debug,Clone)]
-            struct Person {
-                pub name: String,
-                pub age: String,
-                pub email: String,
-            }
-
-            let person: Person = serde_json::from_str(&choice.message.content)?;
+    // call_json 会开启 json_object 输出格式，并在解析失败时让模型修正
+    // 开启 `derive` feature 后可以为 Person 派生 ToolSchema，
+    // 再通过 JsonOptions::for_type::<Person>() 把 Schema 写入提示词
+    let response = client
+        .generation()
+        .call_json::<Person>(request)
+        .await?;
+    let person = response.value;
 
-            println!("{person:?}");
+    println!("{person:?}");
 
-            assert_eq!(person.name, "刘五");
-            assert_eq!(person.age, "34岁");
-            assert_eq!(person.email, "liuwu@example.com");
-        }
-    }
+    assert_eq!(person.name, "刘五");
+    assert_eq!(person.age, "34岁");
+    assert_eq!(person.email, "liuwu@example.com");
     Ok(())
 }
@@ -1,14 +1,28 @@
-use std::{fmt::Debug, pin::Pin};
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
 
 use async_stream::try_stream;
 use bytes::Bytes;
+use backoff::backoff::Backoff as _;
+use reqwest::header::{AUTHORIZATION, HeaderValue};
 use reqwest_eventsource::{Event, EventSource, RequestBuilderExt as _};
 use serde::{Serialize, de::DeserializeOwned};
 use tokio_stream::{Stream, StreamExt as _};
 
 use crate::{
     config::Config,
+    credential::CredentialProvider,
     error::{ApiError, DashScopeError, map_deserialization_error},
+    middleware::{Middleware, MiddlewareChain, ResponseParts},
+    operation::{generation::ContextManager, request::RequestOptions},
+    rate_limit::{RateLimiter, request_model, response_usage},
+    retry::RetryPolicy,
+    usage::UsageTracker,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -16,6 +30,10 @@ pub struct Client {
     pub(crate) http_client: reqwest::Client,
     pub(crate) config: Config,
     pub(crate) backoff: backoff::ExponentialBackoff,
+    pub(crate) middleware: MiddlewareChain,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) context_manager: Option<ContextManager>,
+    pub(crate) usage_tracker: Option<UsageTracker>,
 }
 
 impl Client {
@@ -25,9 +43,13 @@ impl Client {
 
     pub fn with_config(config: Config) -> Self {
         Self {
-            http_client: reqwest::Client::new(),
+            http_client: config.http_client(),
             config,
             backoff: backoff::ExponentialBackoff::default(),
+            middleware: MiddlewareChain::default(),
+            rate_limiter: None,
+            context_manager: None,
+            usage_tracker: None,
         }
     }
     pub fn with_api_key(mut self, api_key: String) -> Self {
@@ -35,6 +57,61 @@ impl Client {
         self
     }
 
+    /// 设置 API Key 提供者，详见 [`CredentialProvider`]
+    pub fn with_credential_provider<P>(mut self, provider: P) -> Self
+    where
+        P: CredentialProvider + 'static,
+    {
+        self.config
+            .set_credential_provider(std::sync::Arc::new(provider));
+        self
+    }
+
+    /// 设置请求重试策略，详见 [`RetryPolicy`]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.config.set_retry_policy(retry_policy);
+        self
+    }
+
+    /// 注册一个请求中间件
+    ///
+    /// 中间件对该客户端（及其克隆）发出的所有请求生效，按注册顺序执行，
+    /// 详见 [`Middleware`]。
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middleware.push(std::sync::Arc::new(middleware));
+        self
+    }
+
+    /// 设置客户端限流器，详见 [`RateLimiter`]
+    ///
+    /// 限流器的状态在该客户端的所有克隆之间共享。
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// 设置上下文管理器，文本生成请求发送前会按模型上下文长度截断历史消息，
+    /// 详见 [`ContextManager`]
+    pub fn with_context_manager(mut self, context_manager: ContextManager) -> Self {
+        self.context_manager = Some(context_manager);
+        self
+    }
+
+    /// 设置用量统计器，详见 [`UsageTracker`]
+    ///
+    /// 统计器的状态在该客户端的所有克隆之间共享。
+    pub fn with_usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
+    pub fn usage_tracker(&self) -> Option<&UsageTracker> {
+        self.usage_tracker.as_ref()
+    }
+
     pub fn build(
         http_client: reqwest::Client,
         config: Config,
@@ -44,6 +121,10 @@ impl Client {
             http_client,
             config,
             backoff,
+            middleware: MiddlewareChain::default(),
+            rate_limiter: None,
+            context_manager: None,
+            usage_tracker: None,
         }
     }
 
@@ -63,6 +144,11 @@ impl Client {
         crate::operation::generation::Generation::new(self)
     }
 
+    /// 获取服务端分词接口，用于在发送请求前精确计算 Token 数
+    pub fn tokenization(&self) -> crate::operation::tokenization::Tokenization<'_> {
+        crate::operation::tokenization::Tokenization::new(self)
+    }
+
     /// 启发多模态对话的功能
     ///
     /// 该函数提供了与多模态对话相关的操作入口
@@ -147,54 +233,39 @@ impl Client {
         crate::operation::embeddings::Embeddings::new(self)
     }
 
-    pub(crate) async fn post_stream<I, O>(
-        &self,
-        path: &str,
-        request: I,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<O, DashScopeError>> + Send>>, DashScopeError>
-    where
-        I: Serialize + Debug,
-        O: DeserializeOwned + std::marker::Send + 'static,
-    {
-        self.post_stream_with_headers(path, request, self.config.headers())
-            .await
-    }
-
-    pub(crate) async fn post_stream_with_headers<I, O>(
+    /// 发送带有单次请求选项的流式 POST 请求
+    pub(crate) async fn post_stream_with_options<I, O>(
         &self,
         path: &str,
         request: I,
         headers: reqwest::header::HeaderMap,
+        options: &RequestOptions,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<O, DashScopeError>> + Send>>, DashScopeError>
     where
         I: Serialize + Debug,
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let event_source = self
+        let template = self
             .http_client
             .post(self.config.url(path))
             .headers(headers)
             .json(&request)
-            .eventsource()?;
+            .build()?;
 
-        Ok(stream(event_source).await)
-    }
+        // 首次连接的请求在这里准备，认证或中间件失败时直接返回错误
+        let first = prepare_stream_request(self, &template, options)
+            .inspect_err(|e| self.middleware.on_error(e))?;
 
-    pub(crate) async fn post<I, O>(&self, path: &str, request: I) -> Result<O, DashScopeError>
-    where
-        I: Serialize + Debug,
-        O: DeserializeOwned,
-    {
-        self.post_with_headers(path, request, self.config().headers())
-            .await
+        Ok(stream(self.clone(), template, first, options.clone()).await)
     }
 
-    /// 发送带有自定义请求头的 POST 请求
+    /// 发送带有自定义请求头和单次请求选项的 POST 请求
     ///
     /// # 参数
     /// * `path` - API 路径
     /// * `request` - 要发送的请求体，需要实现 Serialize 和 Debug trait
     /// * `headers` - 自定义请求头
+    /// * `options` - 单次请求选项
     ///
     /// # 返回值
     /// 返回解析后的响应数据，类型由调用方指定
@@ -204,11 +275,12 @@ impl Client {
     ///
     /// # 注意事项
     /// 此函数是 crate 内部使用的工具函数，不对外公开
-    pub(crate) async fn post_with_headers<I, O>(
+    pub(crate) async fn post_with_options<I, O>(
         &self,
         path: &str,
         request: I,
         headers: reqwest::header::HeaderMap,
+        options: &RequestOptions,
     ) -> Result<O, DashScopeError>
     where
         I: Serialize + Debug,
@@ -223,16 +295,20 @@ impl Client {
                 .build()?)
         };
 
-        self.execute(request_maker).await
+        self.execute(request_maker, options).await
     }
 
-    async fn execute<O, M, Fut>(&self, request_maker: M) -> Result<O, DashScopeError>
+    async fn execute<O, M, Fut>(
+        &self,
+        request_maker: M,
+        options: &RequestOptions,
+    ) -> Result<O, DashScopeError>
     where
         O: DeserializeOwned,
         M: Fn() -> Fut,
         Fut: core::future::Future<Output = Result<reqwest::Request, DashScopeError>>,
     {
-        let bytes = self.execute_raw(request_maker).await?;
+        let bytes = self.execute_raw(request_maker, options).await?;
 
         let response: O = serde_json::from_slice(bytes.as_ref())
             .map_err(|e| map_deserialization_error(e, bytes.as_ref()))?;
@@ -240,51 +316,150 @@ impl Client {
         Ok(response)
     }
 
-    async fn execute_raw<M, Fut>(&self, request_maker: M) -> Result<Bytes, DashScopeError>
+    pub(crate) async fn execute_raw<M, Fut>(
+        &self,
+        request_maker: M,
+        options: &RequestOptions,
+    ) -> Result<Bytes, DashScopeError>
     where
         M: Fn() -> Fut,
         Fut: core::future::Future<Output = Result<reqwest::Request, DashScopeError>>,
     {
         let client = self.http_client.clone();
+        let policy = self.config.retry_policy();
+        let attempts = AtomicU32::new(0);
+
+        let mut backoff = self.backoff.clone();
+        if let Some(max_elapsed_time) = policy.max_elapsed_time() {
+            backoff.max_elapsed_time = Some(max_elapsed_time);
+        }
+
+        let retry = backoff::future::retry(backoff, || async {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if let Some(meta) = options.meta() {
+                meta.set_attempts(attempt);
+            }
 
-        backoff::future::retry(self.backoff.clone(), || async {
-            let request = request_maker().await.map_err(backoff::Error::Permanent)?;
-            let response = client
-                .execute(request)
-                .await
-                .map_err(DashScopeError::Reqwest)
+            let mut request = request_maker().await.map_err(backoff::Error::Permanent)?;
+            // 每次尝试都重新获取 API Key，被限流的 Key 在重试时可以被替换
+            self.config
+                .authorize(request.headers_mut())
                 .map_err(backoff::Error::Permanent)?;
+            options.apply(&mut request).map_err(backoff::Error::Permanent)?;
+            if request.timeout().is_none() {
+                *request.timeout_mut() = self.config.timeout();
+            }
+            self.middleware
+                .before_request(&mut request)
+                .map_err(backoff::Error::Permanent)?;
+            let authorization = request.headers().get(AUTHORIZATION).cloned();
+            let model = request_model(&request);
+            let _permit = match &self.rate_limiter {
+                Some(limiter) => Some(limiter.acquire(model.as_deref()).await),
+                None => None,
+            };
+
+            let response = match client.execute(request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    let retryable = policy.is_retryable_network_error(&e);
+                    return Err(policy.to_backoff_error(
+                        attempt,
+                        DashScopeError::Reqwest(e),
+                        retryable,
+                        None,
+                    ));
+                }
+            };
 
             let status = response.status();
-            let bytes = response
-                .bytes()
-                .await
-                .map_err(DashScopeError::Reqwest)
+            let headers = response.headers().clone();
+            let bytes = match response.bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    let retryable = policy.is_retryable_network_error(&e);
+                    return Err(policy.to_backoff_error(
+                        attempt,
+                        DashScopeError::Reqwest(e),
+                        retryable,
+                        None,
+                    ));
+                }
+            };
+
+            let mut parts = ResponseParts {
+                status,
+                headers,
+                body: bytes,
+                attempt,
+            };
+            self.middleware
+                .after_response(&mut parts)
                 .map_err(backoff::Error::Permanent)?;
+            let ResponseParts {
+                status,
+                headers,
+                body: bytes,
+                ..
+            } = parts;
 
             // Deserialize response body from either error object or actual response object
             if !status.is_success() {
-                let api_error: ApiError = serde_json::from_slice(bytes.as_ref())
-                    .map_err(|e| map_deserialization_error(e, bytes.as_ref()))
-                    .map_err(backoff::Error::Permanent)?;
-
-                if status.as_u16() == 429 {
-                    // Rate limited retry...
-                    tracing::warn!("Rate limited: {}", api_error.message);
-                    return Err(backoff::Error::Transient {
-                        err: DashScopeError::ApiError(api_error),
-                        retry_after: None,
-                    });
-                } else {
-                    return Err(backoff::Error::Permanent(DashScopeError::ApiError(
-                        api_error,
-                    )));
+                let api_error = ApiError::from_response(status, bytes.as_ref());
+                if api_error.is_throttling() {
+                    self.config.report_throttled(authorization.as_ref());
+                }
+
+                let retryable = policy.is_retryable_response(status, api_error.code.as_deref());
+                if retryable {
+                    tracing::warn!("Transient error ({status}): {}", api_error.message);
+                }
+                return Err(policy.to_backoff_error(
+                    attempt,
+                    DashScopeError::ApiError(api_error),
+                    retryable,
+                    policy.retry_after(&headers),
+                ));
+            }
+
+            if attempt > 1 {
+                tracing::debug!("request succeeded on attempt {attempt}");
+            }
+
+            let limit_tokens = matches!(
+                (&self.rate_limiter, model.as_deref()),
+                (Some(limiter), Some(model)) if limiter.quota(model).is_some_and(|q| q.tpm.is_some())
+            );
+            let usage = if limit_tokens || self.usage_tracker.is_some() {
+                serde_json::from_slice(bytes.as_ref())
+                    .ok()
+                    .and_then(|v| response_usage(&v))
+            } else {
+                None
+            };
+            if let Some(usage) = usage {
+                if let (Some(limiter), Some(model)) = (&self.rate_limiter, model.as_deref()) {
+                    limiter.record_usage(model, &usage);
+                }
+                // 任务查询等 GET 请求没有模型名称，不计入用量统计
+                if let (Some(tracker), Some(model)) = (&self.usage_tracker, model.as_deref()) {
+                    tracker.record(model, options.tags(), &usage);
                 }
             }
 
             Ok(bytes)
-        })
-        .await
+        });
+
+        let result = match options.cancellation() {
+            Some(token) => tokio::select! {
+                biased;
+                _ = token.cancelled() => Err(DashScopeError::Cancelled),
+                result = retry => result,
+            },
+            None => retry.await,
+        };
+
+        result.inspect_err(|e| self.middleware.on_error(e))
     }
 
     pub fn config(&self) -> &Config {
@@ -296,6 +471,7 @@ impl Client {
     /// # 参数
     /// * `path` - API 路径
     /// * `form_fn` - 返回 multipart 表单数据的函数
+    /// * `options` - 单次请求选项
     ///
     /// # 返回值
     /// 返回响应结果，类型由调用方指定
@@ -309,6 +485,7 @@ impl Client {
         &self,
         path: &str,
         form_fn: F,
+        options: &RequestOptions,
     ) -> Result<O, DashScopeError>
     where
         O: DeserializeOwned,
@@ -326,7 +503,7 @@ impl Client {
                 .build()?)
         };
 
-        self.execute(request_maker).await
+        self.execute(request_maker, options).await
     }
 
     /// 发送带查询参数的 GET 请求
@@ -334,6 +511,7 @@ impl Client {
     /// # 参数
     /// * `path` - API 路径
     /// * `params` - 查询参数
+    /// * `options` - 单次请求选项
     ///
     /// # 返回值
     /// 返回响应结果，类型由调用方指定
@@ -343,7 +521,12 @@ impl Client {
     ///
     /// # 注意事项
     /// 此函数是 crate 内部使用的工具函数，不对外公开
-    pub(crate) async fn get_with_params<O, P>(&self, path: &str, params: &P) -> Result<O, DashScopeError>
+    pub(crate) async fn get_with_params<O, P>(
+        &self,
+        path: &str,
+        params: &P,
+        options: &RequestOptions,
+    ) -> Result<O, DashScopeError>
     where
         O: DeserializeOwned,
         P: serde::Serialize + ?Sized,
@@ -357,13 +540,14 @@ impl Client {
                 .build()?)
         };
 
-        self.execute(request_maker).await
+        self.execute(request_maker, options).await
     }
 
     /// 发送 DELETE 请求
     ///
     /// # 参数
     /// * `path` - API 路径
+    /// * `options` - 单次请求选项
     ///
     /// # 返回值
     /// 返回响应结果，类型由调用方指定
@@ -373,7 +557,11 @@ impl Client {
     ///
     /// # 注意事项
     /// 此函数是 crate 内部使用的工具函数，不对外公开
-    pub(crate) async fn delete<O>(&self, path: &str) -> Result<O, DashScopeError>
+    pub(crate) async fn delete<O>(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<O, DashScopeError>
     where
         O: DeserializeOwned,
     {
@@ -385,33 +573,347 @@ impl Client {
                 .build()?)
         };
 
-        self.execute(request_maker).await
+        self.execute(request_maker, options).await
+    }
+}
+
+/// 基于请求模板准备一次流式连接的请求，用于首次连接以及建立连接阶段的重试
+///
+/// 与 [`Client::execute_raw`] 的每次尝试一致：重新获取 API Key、应用单次请求选项，
+/// 再依次调用中间件的 `before_request`。
+fn prepare_stream_request(
+    client: &Client,
+    template: &reqwest::Request,
+    options: &RequestOptions,
+) -> Result<reqwest::Request, DashScopeError> {
+    let mut request = template.try_clone().ok_or_else(|| {
+        DashScopeError::StreamError("stream request body cannot be cloned".into())
+    })?;
+    client.config.authorize(request.headers_mut())?;
+    options.apply(&mut request)?;
+    client.middleware.before_request(&mut request)?;
+    Ok(request)
+}
+
+/// 使用准备好的请求创建一个新的 `EventSource`
+///
+/// 同时返回本次连接使用的 `Authorization` 请求头，用于反馈限流。
+fn open_event_source(
+    client: &Client,
+    request: reqwest::Request,
+) -> Result<(EventSource, Option<HeaderValue>), DashScopeError> {
+    let authorization = request.headers().get(AUTHORIZATION).cloned();
+    let event_source =
+        reqwest::RequestBuilder::from_parts(client.http_client.clone(), request).eventsource()?;
+    Ok((event_source, authorization))
+}
+
+/// 将 `EventSource` 的错误转换为 `DashScopeError`，并判断是否可以重试
+async fn map_event_source_error(
+    error: reqwest_eventsource::Error,
+    policy: &RetryPolicy,
+) -> (DashScopeError, bool) {
+    match error {
+        reqwest_eventsource::Error::Transport(e) => {
+            let retryable = policy.is_retryable_network_error(&e);
+            (DashScopeError::Reqwest(e), retryable)
+        }
+        reqwest_eventsource::Error::InvalidStatusCode(status, response) => {
+            let bytes = response.bytes().await.unwrap_or_default();
+            let api_error = ApiError::from_response(status, bytes.as_ref());
+            let retryable = policy.is_retryable_response(status, api_error.code.as_deref());
+            (DashScopeError::ApiError(api_error), retryable)
+        }
+        reqwest_eventsource::Error::StreamEnded => {
+            (DashScopeError::StreamError("stream ended".into()), true)
+        }
+        e => (DashScopeError::StreamError(e.to_string()), false),
+    }
+}
+
+/// 流式读取过程中的失败原因
+enum StreamFailure {
+    EventSource(Box<reqwest_eventsource::Error>),
+    IdleTimeout(Duration),
+}
+
+/// 流式输出的结束状态
+///
+/// 请求参数 `n` 大于 1 时，各候选回复交错下发，每个数据块只包含其中一部分 `choices`，
+/// 需要等所有候选都给出 `finish_reason` 后才算结束。
+struct StreamProgress {
+    choices: usize,
+    finished: BTreeSet<u64>,
+}
+
+impl StreamProgress {
+    fn new(choices: usize) -> Self {
+        Self {
+            choices: choices.max(1),
+            finished: BTreeSet::new(),
+        }
+    }
+
+    /// 记录一个流式数据块，返回它是否为最后一个数据块
+    ///
+    /// - 文本生成与多模态：所有候选的 `choices[].finish_reason` 都非空（流式过程中该字段为 `"null"` 字符串），
+    ///   候选按 `index` 区分，缺失时按在数组中的位置；
+    /// - `result_format` 为 `text` 时的 `output.finish_reason`，语音合成同样使用该字段；
+    /// - 语音合成：`output.audio.url` 出现时表示音频已全部生成。
+    fn update(&mut self, value: &serde_json::Value) -> bool {
+        let is_reason = |v: &serde_json::Value| {
+            v.as_str()
+                .is_some_and(|reason| !reason.is_empty() && reason != "null")
+        };
+
+        if let Some(choices) = value.pointer("/output/choices").and_then(|v| v.as_array()) {
+            for (position, choice) in choices.iter().enumerate() {
+                if choice.get("finish_reason").is_some_and(is_reason) {
+                    self.finished.insert(choice_index(choice, position));
+                }
+            }
+        }
+
+        self.finished.len() >= self.choices
+            || value.pointer("/output/finish_reason").is_some_and(is_reason)
+            || value
+                .pointer("/output/audio/url")
+                .and_then(|v| v.as_str())
+                .is_some_and(|url| !url.is_empty())
+    }
+}
+
+fn choice_index(choice: &serde_json::Value, position: usize) -> u64 {
+    choice
+        .get("index")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(position as u64)
+}
+
+/// 从 JSON 请求体中提取候选回复的数量 `parameters.n`，未设置时为 1
+fn request_choices(request: &reqwest::Request) -> usize {
+    request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .and_then(|body| serde_json::from_slice::<serde_json::Value>(body).ok())
+        .and_then(|value| value.pointer("/parameters/n").and_then(|n| n.as_u64()))
+        .map_or(1, |n| n as usize)
+}
+
+fn has_usage(value: &serde_json::Value) -> bool {
+    value.get("usage").is_some_and(|usage| !usage.is_null())
+}
+
+/// 提取一个流式数据块中的增量文本，用于在流中断时返回已接收的部分输出
+///
+/// 有多个候选回复时只取第一个候选（`index` 为 0）的内容。
+fn delta_text(value: &serde_json::Value) -> Option<String> {
+    if let Some(choices) = value.pointer("/output/choices").and_then(|v| v.as_array()) {
+        let content = choices
+            .iter()
+            .enumerate()
+            .find(|(position, choice)| choice_index(choice, *position) == 0)
+            .and_then(|(_, choice)| choice.pointer("/message/content"))?;
+        return match content {
+            serde_json::Value::String(s) => Some(s.clone()),
+            // 多模态模型的 content 为数组：[{"text": "..."}]
+            serde_json::Value::Array(items) => Some(
+                items
+                    .iter()
+                    .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+                    .collect(),
+            ),
+            _ => None,
+        };
     }
+    value
+        .pointer("/output/text")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
 }
 
+/// 收到结束标记后等待 usage 数据块的最长时间
+const STREAM_FINISH_GRACE: Duration = Duration::from_secs(3);
+
 pub(crate) async fn stream<O>(
-    mut event_source: EventSource,
+    client: Client,
+    request: reqwest::Request,
+    first: reqwest::Request,
+    options: RequestOptions,
 ) -> Pin<Box<dyn Stream<Item = Result<O, DashScopeError>> + Send>>
 where
     O: DeserializeOwned + std::marker::Send + 'static,
 {
+    let cancellation = options.cancellation().cloned();
+    let meta = options.meta().cloned();
     let stream = try_stream! {
-        while let Some(ev) = event_source.next().await {
+        let policy = client.config.retry_policy().clone();
+        let mut backoff = client.backoff.clone();
+        backoff.reset();
+        let mut attempt: u32 = 1;
+        let mut received_events: usize = 0;
+        let mut partial_output = String::new();
+
+        let idle_timeout = client.config.stream_idle_timeout();
+        // 收到结束标记后，最多再等待这么久以接收单独下发的 usage 数据块
+        let finish_grace = idle_timeout.map_or(STREAM_FINISH_GRACE, |d| d.min(STREAM_FINISH_GRACE));
+        let mut finished = false;
+        let mut progress = StreamProgress::new(request_choices(&request));
+
+        let model = request_model(&request);
+        let mut debited_tokens: u64 = 0;
+        let mut tracked_usage = None;
+        let mut _permit = match &client.rate_limiter {
+            Some(limiter) => Some(limiter.acquire(model.as_deref()).await),
+            None => None,
+        };
+        if let Some(meta) = &meta {
+            meta.set_attempts(attempt);
+        }
+        let (mut event_source, mut authorization) = open_event_source(&client, first)?;
+
+        loop {
+            let wait = if finished { Some(finish_grace) } else { idle_timeout };
+            let next_event = async {
+                match wait {
+                    Some(wait) => match tokio::time::timeout(wait, event_source.next()).await {
+                        Ok(ev) => ev.map(|ev| ev.map_err(|e| StreamFailure::EventSource(Box::new(e)))),
+                        Err(_) => Some(Err(StreamFailure::IdleTimeout(wait))),
+                    },
+                    None => event_source
+                        .next()
+                        .await
+                        .map(|ev| ev.map_err(|e| StreamFailure::EventSource(Box::new(e)))),
+                }
+            };
+            let next = match &cancellation {
+                Some(token) => tokio::select! {
+                    biased;
+                    _ = token.cancelled() => None,
+                    next = next_event => next,
+                },
+                None => next_event.await,
+            };
+
+            let ev = match next {
+                None if cancellation.as_ref().is_some_and(|t| t.is_cancelled()) => {
+                    event_source.close();
+                    let err = DashScopeError::Cancelled;
+                    client.middleware.on_error(&err);
+                    Err(err)?;
+                    break;
+                }
+                None => break,
+                // 已经收到结束标记，服务端关闭连接或超时都视为正常结束
+                Some(Err(_)) if finished => break,
+                Some(ev) => ev,
+            };
+
             match ev {
-                Err(e) => {
-                    Err(DashScopeError::StreamError(e.to_string()))?;
+                Err(failure) => {
+                    event_source.close();
+                    let (err, retryable) = match failure {
+                        StreamFailure::EventSource(e) => map_event_source_error(*e, &policy).await,
+                        StreamFailure::IdleTimeout(wait) => (
+                            DashScopeError::TimeoutError(format!(
+                                "no stream event received within {wait:?}"
+                            )),
+                            true,
+                        ),
+                    };
+                    if err.api_error().is_some_and(|e| e.is_throttling()) {
+                        client.config.report_throttled(authorization.as_ref());
+                    }
+
+                    if received_events == 0 {
+                        // 尚未收到任何数据，可以安全地重新建立连接
+                        if policy.retry_stream_connect()
+                            && retryable
+                            && policy.has_attempts_left(attempt)
+                        {
+                            if let Some(delay) = backoff.next_backoff() {
+                                tracing::warn!("stream attempt {attempt} failed, retrying: {err}");
+                                tokio::time::sleep(delay).await;
+                                attempt += 1;
+                                if let Some(meta) = &meta {
+                                    meta.set_attempts(attempt);
+                                }
+                                if let Some(limiter) = &client.rate_limiter {
+                                    drop(_permit.take());
+                                    _permit = Some(limiter.acquire(model.as_deref()).await);
+                                }
+                                let retry = prepare_stream_request(&client, &request, &options)
+                                    .inspect_err(|e| client.middleware.on_error(e))?;
+                                (event_source, authorization) = open_event_source(&client, retry)?;
+                                continue;
+                            }
+                        }
+
+                        let err = DashScopeError::StreamConnect {
+                            source: Box::new(err),
+                            attempts: attempt,
+                        };
+                        client.middleware.on_error(&err);
+                        Err(err)?;
+                    } else {
+                        let err = DashScopeError::StreamInterrupted {
+                            source: Box::new(err),
+                            received_events,
+                            partial_output: std::mem::take(&mut partial_output),
+                        };
+                        client.middleware.on_error(&err);
+                        Err(err)?;
+                    }
                 }
                 Ok(Event::Open) => continue,
                 Ok(Event::Message(message)) => {
+                    received_events += 1;
+
                     // First, deserialize to a generic JSON Value to inspect it without failing.
                     let json_value: serde_json::Value = match serde_json::from_str(&message.data) {
                         Ok(val) => val,
+                        Err(_) if message.event == "error" => {
+                            event_source.close();
+                            let err = DashScopeError::ApiError(ApiError::new(message.data.clone()));
+                            client.middleware.on_error(&err);
+                            Err(err)?;
+                            continue;
+                        }
                         Err(e) => {
                             Err(map_deserialization_error(e, message.data.as_bytes()))?;
                             continue;
                         }
                     };
 
+                    // DashScope 在流中返回的错误（例如内容审核未通过）
+                    if let Some(api_error) = ApiError::from_stream_event(&message.event, &json_value) {
+                        event_source.close();
+                        let err = DashScopeError::ApiError(api_error);
+                        client.middleware.on_error(&err);
+                        Err(err)?;
+                    }
+
+                    // 流式数据块中的 usage 是累计值，只扣减新增的部分
+                    if let Some(usage) = response_usage(&json_value) {
+                        if let (Some(limiter), Some(model)) = (&client.rate_limiter, model.as_deref()) {
+                            let total = usage.total().max(0) as u64;
+                            limiter.record_tokens(model, total.saturating_sub(debited_tokens));
+                            debited_tokens = debited_tokens.max(total);
+                        }
+                        if let (Some(tracker), Some(model)) = (&client.usage_tracker, model.as_deref()) {
+                            tracked_usage = Some(tracker.record_delta(
+                                model,
+                                options.tags(),
+                                &usage,
+                                tracked_usage.as_ref(),
+                            ));
+                        }
+                    }
+
+                    if let Some(text) = delta_text(&json_value) {
+                        partial_output.push_str(&text);
+                    }
+
                     // Now, deserialize from the `Value` to the target type `O`.
                     let response = serde_json::from_value::<O>(json_value.clone())
                         .map_err(|e| map_deserialization_error(e, message.data.as_bytes()))?;
@@ -419,15 +921,19 @@ where
                     // Yield the successful message
                     yield response;
 
-                    // Check for finish reason after sending the message.
-                    // This ensures the final message with "stop" is delivered.
-                    let finish_reason = json_value
-                        .pointer("/output/choices/0/finish_reason")
-                        .and_then(|v| v.as_str());
-
-                    if let Some("stop") = finish_reason {
+                    // 结束标记之后单独下发的 usage 数据块已经送达
+                    if finished {
                         break;
                     }
+
+                    // Check for finish reason after sending the message.
+                    // This ensures the final message is delivered.
+                    if progress.update(&json_value) {
+                        if has_usage(&json_value) {
+                            break;
+                        }
+                        finished = true;
+                    }
                 }
             }
         }
@@ -457,4 +963,115 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_stream_progress() {
+        let is_stream_finished = |value: &serde_json::Value| StreamProgress::new(1).update(value);
+
+        let generating = serde_json::json!({
+            "output": {"choices": [{"finish_reason": "null", "message": {"content": "a"}}]}
+        });
+        assert!(!is_stream_finished(&generating));
+
+        let length = serde_json::json!({
+            "output": {"choices": [{"finish_reason": "length", "message": {"content": "a"}}]}
+        });
+        assert!(is_stream_finished(&length));
+
+        let tool_calls = serde_json::json!({
+            "output": {"choices": [
+                {"finish_reason": null, "message": {"content": ""}},
+                {"finish_reason": "tool_calls", "message": {"content": ""}}
+            ]}
+        });
+        assert!(is_stream_finished(&tool_calls));
+
+        let text = serde_json::json!({"output": {"text": "", "finish_reason": "stop"}});
+        assert!(is_stream_finished(&text));
+
+        let tts = serde_json::json!({
+            "output": {"finish_reason": null, "audio": {"url": "https://example.com/a.wav", "data": ""}}
+        });
+        assert!(is_stream_finished(&tts));
+
+        let tts_generating = serde_json::json!({
+            "output": {"finish_reason": "null", "audio": {"url": "", "data": "AAAA"}}
+        });
+        assert!(!is_stream_finished(&tts_generating));
+
+        // n = 2 时两个候选都结束才算结束
+        let mut progress = StreamProgress::new(2);
+        let chunk = |index: u32, reason: &str| {
+            serde_json::json!({
+                "output": {"choices": [{"index": index, "finish_reason": reason, "message": {"content": "a"}}]}
+            })
+        };
+        assert!(!progress.update(&chunk(1, "null")));
+        assert!(!progress.update(&chunk(1, "stop")));
+        assert!(!progress.update(&chunk(1, "stop")));
+        assert!(!progress.update(&chunk(0, "null")));
+        assert!(progress.update(&chunk(0, "length")));
+    }
+
+    #[test]
+    fn test_delta_text() {
+        let text = serde_json::json!({"output": {"choices": [{"message": {"content": "你好"}}]}});
+        assert_eq!(delta_text(&text).as_deref(), Some("你好"));
+
+        let multimodal = serde_json::json!({
+            "output": {"choices": [{"message": {"content": [{"text": "a"}, {"image": "x"}, {"text": "b"}]}}]}
+        });
+        assert_eq!(delta_text(&multimodal).as_deref(), Some("ab"));
+
+        let text_format = serde_json::json!({"output": {"text": "hi", "finish_reason": "null"}});
+        assert_eq!(delta_text(&text_format).as_deref(), Some("hi"));
+
+        let tts = serde_json::json!({"output": {"audio": {"data": ""}}});
+        assert_eq!(delta_text(&tts), None);
+
+        let second = serde_json::json!({"output": {"choices": [{"index": 1, "message": {"content": "b"}}]}});
+        assert_eq!(delta_text(&second), None);
+    }
+
+    #[test]
+    fn test_prepare_stream_request_runs_middleware() {
+        use std::sync::{Arc, atomic::AtomicUsize};
+
+        #[derive(Default)]
+        struct Counter(AtomicUsize);
+
+        impl crate::middleware::Middleware for Arc<Counter> {
+            fn before_request(&self, request: &mut reqwest::Request) -> crate::error::Result<()> {
+                let n = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                request
+                    .headers_mut()
+                    .insert("X-Attempt", HeaderValue::from(n));
+                Ok(())
+            }
+        }
+
+        let counter = Arc::new(Counter::default());
+        let config = ConfigBuilder::default()
+            .api_key("test key")
+            .build()
+            .unwrap();
+        let client = Client::with_config(config).with_middleware(counter.clone());
+        let template = client
+            .http_client
+            .post(client.config.url("files"))
+            .headers(client.config.headers())
+            .json(&serde_json::json!({"model": "qwen-plus"}))
+            .build()
+            .unwrap();
+
+        // 首次连接与每次重连都要重新走一遍中间件
+        for attempt in 1..=2 {
+            let request =
+                prepare_stream_request(&client, &template, &RequestOptions::default()).unwrap();
+            assert_eq!(request.headers()["X-Attempt"], attempt.to_string().as_str());
+            assert_eq!(request.headers()[AUTHORIZATION], "Bearer test key");
+        }
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert!(template.headers().get("X-Attempt").is_none());
+    }
 }
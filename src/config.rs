@@ -12,12 +12,31 @@
 
 // todo: Qwen3-Coder 暂不支持 dashscope 的基于 Partial Mode 的代码补全功能
 
+use std::{sync::Arc, time::Duration};
+
 use derive_builder::Builder;
-use reqwest::header::AUTHORIZATION;
+use reqwest::header::{AUTHORIZATION, HeaderValue};
 use secrecy::{ExposeSecret as _, SecretString};
 
+use crate::{
+    credential::CredentialProvider,
+    error::{DashScopeError, Result},
+    retry::RetryPolicy,
+};
+pub use profile::Profile;
+pub use region::Region;
+
+pub mod profile;
+pub mod region;
+
 pub const DASHSCOPE_API_BASE: &str = "https://dashscope.aliyuncs.com/api/v1";
 
+/// 新加坡地域（国际版）的 API 地址
+pub const DASHSCOPE_INTL_API_BASE: &str = "https://dashscope-intl.aliyuncs.com/api/v1";
+
+/// 流式请求默认的空闲超时时间
+pub const DEFAULT_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
 /// # Config
 ///
 /// ```rust
@@ -33,18 +52,79 @@ pub const DASHSCOPE_API_BASE: &str = "https://dashscope.aliyuncs.com/api/v1";
 /// let  client = Client::with_config(conf);
 /// ```
 #[derive(Debug, Builder, Clone)]
-#[builder(setter(into))]
+#[builder(setter(into), build_fn(validate = "Self::validate"))]
 pub struct Config {
     #[builder(setter(into, strip_option))]
     #[builder(default = "self.default_base_url()")]
     api_base: Option<String>,
+    #[builder(default)]
     api_key: SecretString,
+
+    /// API Key 提供者，设置后优先于 `api_key`，每次请求都会重新获取
+    #[builder(setter(custom), default)]
+    credential_provider: Option<Arc<dyn CredentialProvider>>,
+
+    /// 请求重试策略，默认重试 429、5xx 以及网络错误
+    #[builder(default)]
+    retry_policy: RetryPolicy,
+
+    /// 流式请求两个事件之间的最长等待时间，超时后流以错误结束；`None` 表示不限制
+    #[builder(setter(strip_option), default = "Some(DEFAULT_STREAM_IDLE_TIMEOUT)")]
+    stream_idle_timeout: Option<Duration>,
+
+    /// 业务空间 ID，设置后所有请求都会携带 `X-DashScope-WorkSpace` 请求头
+    #[builder(setter(into, strip_option), default)]
+    workspace: Option<String>,
+
+    /// HTTP 代理地址，例如 `http://127.0.0.1:7890`
+    #[builder(setter(into, strip_option), default)]
+    proxy: Option<String>,
+
+    /// 非流式请求的默认超时时间，可被单次请求的 `RequestOptions` 覆盖
+    #[builder(setter(strip_option), default)]
+    timeout: Option<Duration>,
+
+    /// WebSocket 地址，默认由 `api_base` 所在的地域推导
+    #[builder(setter(into, strip_option), default)]
+    ws_url: Option<String>,
 }
 
 impl ConfigBuilder {
     fn default_base_url(&self) -> Option<String> {
         Some(DASHSCOPE_API_BASE.to_string())
     }
+
+    /// 设置服务地域，同时决定 HTTP、WebSocket 和文件上传的地址
+    pub fn region(&mut self, region: Region) -> &mut Self {
+        self.api_base = Some(Some(region.api_base().to_string()));
+        self.ws_url = match region {
+            Region::Custom { ws_url, .. } => Some(ws_url),
+            _ => Some(None),
+        };
+        self
+    }
+
+    /// 设置 API Key 提供者，详见 [`CredentialProvider`]
+    pub fn credential_provider<P>(&mut self, provider: P) -> &mut Self
+    where
+        P: CredentialProvider + 'static,
+    {
+        self.credential_provider = Some(Some(Arc::new(provider)));
+        self
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.api_key.is_none() && !matches!(self.credential_provider, Some(Some(_))) {
+            return Err("`api_key` or `credential_provider` must be initialized".into());
+        }
+        if let Some(api_key) = &self.api_key {
+            bearer(api_key).map_err(|e| e.to_string())?;
+        }
+        if let Some(Some(proxy)) = &self.proxy {
+            reqwest::Proxy::all(proxy).map_err(|e| format!("invalid proxy {proxy}: {e}"))?;
+        }
+        Ok(())
+    }
 }
 
 impl Config {
@@ -58,6 +138,40 @@ impl Config {
         );
         n_url.trim_end_matches('/').to_string()
     }
+
+    /// 当前配置对应的地域
+    pub fn region(&self) -> Region {
+        let api_base = self.api_base.as_deref().unwrap_or(DASHSCOPE_API_BASE);
+        match (Region::from_api_base(api_base), &self.ws_url) {
+            (Region::Custom { api_base, .. }, ws_url) => Region::Custom {
+                api_base,
+                ws_url: ws_url.clone(),
+            },
+            (region, Some(ws_url)) if *ws_url != region.ws_url() => Region::Custom {
+                api_base: api_base.to_string(),
+                ws_url: Some(ws_url.clone()),
+            },
+            (region, _) => region,
+        }
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        self.api_base = Some(region.api_base().to_string());
+        self.ws_url = match region {
+            Region::Custom { ws_url, .. } => ws_url,
+            _ => None,
+        };
+    }
+
+    /// WebSocket 推理接口地址
+    pub fn ws_url(&self) -> String {
+        self.region().ws_url()
+    }
+
+    /// 获取临时文件上传凭证的地址
+    pub fn upload_url(&self) -> String {
+        self.url("uploads")
+    }
     pub fn headers(&self) -> reqwest::header::HeaderMap {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("Content-Type", "application/json".parse().unwrap());
@@ -65,22 +179,121 @@ impl Config {
             "X-DashScope-OssResourceResolve",
             "enable".parse().unwrap(),
         );
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {}", self.api_key.expose_secret())
-                .parse()
-                .unwrap(),
-        );
+        // 设置了 API Key 提供者时，由客户端在每次发送请求前调用 `authorize` 获取；
+        // 无法作为请求头的 API Key 在这里跳过，由 `authorize` 返回错误
+        if self.credential_provider.is_none() {
+            if let Ok(value) = bearer(&self.api_key) {
+                headers.insert(AUTHORIZATION, value);
+            }
+        }
+        if let Some(workspace) = self.workspace.as_deref().and_then(|w| w.parse().ok()) {
+            headers.insert("X-DashScope-WorkSpace", workspace);
+        }
         headers
     }
 
+    /// 根据代理等配置创建 HTTP 客户端
+    pub(crate) fn http_client(&self) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = self.proxy.as_deref() {
+            match reqwest::Proxy::all(proxy) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(e) => tracing::warn!("ignore invalid proxy {proxy}: {e}"),
+            }
+        }
+        builder.build().unwrap_or_else(|e| {
+            tracing::warn!("failed to build http client, fallback to default: {e}");
+            reqwest::Client::new()
+        })
+    }
+
+    /// 设置固定的 API Key，同时移除已设置的 API Key 提供者
     pub fn set_api_key(&mut self, api_key: SecretString) {
         self.api_key = api_key;
+        self.credential_provider = None;
     }
-    
+
+    /// 固定的 API Key，设置了 API Key 提供者时不会使用
     pub fn api_key(&self) -> &SecretString {
         &self.api_key
     }
+
+    /// 设置了 API Key 提供者时，向其获取 API Key 并写入请求头
+    ///
+    /// 客户端在每次发送请求（包括重试）前调用，未设置提供者时 `headers` 中通常已包含固定的 API Key。
+    /// API Key 含有换行等不能出现在请求头中的字符时返回 [`DashScopeError::InvalidArgument`]。
+    pub(crate) fn authorize(&self, headers: &mut reqwest::header::HeaderMap) -> Result<()> {
+        match &self.credential_provider {
+            Some(provider) => {
+                headers.insert(AUTHORIZATION, bearer(&provider.api_key())?);
+            }
+            None if !headers.contains_key(AUTHORIZATION) => {
+                headers.insert(AUTHORIZATION, bearer(&self.api_key)?);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    pub fn set_credential_provider(&mut self, provider: Arc<dyn CredentialProvider>) {
+        self.credential_provider = Some(provider);
+    }
+
+    pub fn credential_provider(&self) -> Option<&Arc<dyn CredentialProvider>> {
+        self.credential_provider.as_ref()
+    }
+
+    /// 将请求被限流的情况反馈给 API Key 提供者
+    pub(crate) fn report_throttled(&self, authorization: Option<&HeaderValue>) {
+        let Some(provider) = &self.credential_provider else {
+            return;
+        };
+        if let Some(api_key) = authorization
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+        {
+            provider.on_throttled(api_key);
+        }
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn set_stream_idle_timeout(&mut self, stream_idle_timeout: Option<Duration>) {
+        self.stream_idle_timeout = stream_idle_timeout;
+    }
+
+    pub fn stream_idle_timeout(&self) -> Option<Duration> {
+        self.stream_idle_timeout
+    }
+
+    pub fn workspace(&self) -> Option<&str> {
+        self.workspace.as_deref()
+    }
+
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+fn bearer(api_key: &SecretString) -> Result<HeaderValue> {
+    let mut value =
+        HeaderValue::from_str(&format!("Bearer {}", api_key.expose_secret())).map_err(|_| {
+            DashScopeError::InvalidArgument(
+                "API key contains characters that are not allowed in an HTTP header".into(),
+            )
+        })?;
+    value.set_sensitive(true);
+    Ok(value)
 }
 
 impl Default for Config {
@@ -90,6 +303,13 @@ impl Default for Config {
             api_key: std::env::var("DASHSCOPE_API_KEY")
                 .unwrap_or_else(|_| "".to_string())
                 .into(),
+            credential_provider: None,
+            retry_policy: RetryPolicy::default(),
+            stream_idle_timeout: Some(DEFAULT_STREAM_IDLE_TIMEOUT),
+            workspace: None,
+            proxy: None,
+            timeout: None,
+            ws_url: None,
         }
     }
 }
@@ -159,4 +379,68 @@ mod tests {
             "Bearer test"
         );
     }
+
+    #[test]
+    fn test_region_endpoints() {
+        let instance = ConfigBuilder::default()
+            .region(Region::ApSoutheast1)
+            .api_key("test")
+            .build()
+            .unwrap();
+        assert_eq!(instance.region(), Region::ApSoutheast1);
+        assert_eq!(
+            instance.upload_url(),
+            format!("{DASHSCOPE_INTL_API_BASE}/uploads")
+        );
+        assert_eq!(instance.ws_url(), region::DASHSCOPE_INTL_WS_URL);
+
+        let instance = ConfigBuilder::default()
+            .api_base("http://localhost:8080")
+            .api_key("test")
+            .build()
+            .unwrap();
+        assert_eq!(instance.upload_url(), "http://localhost:8080/uploads");
+        assert_eq!(instance.ws_url(), "ws://localhost:8080/inference");
+    }
+
+    #[test]
+    fn test_credential_provider() {
+        use crate::credential::{KeyPool, PoolStrategy};
+
+        assert!(ConfigBuilder::default().build().is_err());
+
+        let mut instance = ConfigBuilder::default()
+            .credential_provider(KeyPool::new(["a", "b"], PoolStrategy::RoundRobin).unwrap())
+            .build()
+            .unwrap();
+        // 构造请求头不会消耗提供者中的 API Key
+        assert!(instance.headers().get("Authorization").is_none());
+        let mut headers = instance.headers();
+        instance.authorize(&mut headers).unwrap();
+        assert_eq!(headers["Authorization"], "Bearer a");
+        instance.authorize(&mut headers).unwrap();
+        assert_eq!(headers["Authorization"], "Bearer b");
+
+        instance.set_api_key("c".into());
+        assert_eq!(instance.headers()["Authorization"], "Bearer c");
+    }
+
+    #[test]
+    fn test_invalid_api_key() {
+        use crate::credential::StaticCredential;
+
+        assert!(ConfigBuilder::default().api_key("sk-a\n").build().is_err());
+
+        let mut instance = ConfigBuilder::default().api_key("sk-a").build().unwrap();
+        instance.set_api_key("sk-a\n".into());
+        let mut headers = instance.headers();
+        assert!(headers.get("Authorization").is_none());
+        assert!(matches!(
+            instance.authorize(&mut headers),
+            Err(DashScopeError::InvalidArgument(_))
+        ));
+
+        instance.set_credential_provider(Arc::new(StaticCredential::new("sk-b\r")));
+        assert!(instance.authorize(&mut headers).is_err());
+    }
 }
@@ -0,0 +1,268 @@
+//! 从环境变量和配置文件加载 [`Config`]
+//!
+//! 配置文件支持 TOML 与 JSON（按扩展名区分），每个顶层表为一个 profile：
+//!
+//! ```toml
+//! [default]
+//! api_key = "sk-xxx"
+//!
+//! [intl]
+//! api_key = "sk-yyy"
+//! region = "ap-southeast-1"
+//!
+//! [staging]
+//! api_base = "https://staging.example.com/api/v1"
+//! workspace = "ws-xxx"
+//! proxy = "http://127.0.0.1:7890"
+//! timeout = 30
+//! ```
+use std::{collections::HashMap, path::Path, path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+use super::{Config, ConfigBuilder, Region};
+use crate::credential::FileCredential;
+use crate::error::{DashScopeError, Result};
+
+pub const ENV_API_KEY: &str = "DASHSCOPE_API_KEY";
+/// 保存 API Key 的文件路径，文件内容变化后自动生效
+pub const ENV_API_KEY_FILE: &str = "DASHSCOPE_API_KEY_FILE";
+pub const ENV_API_BASE: &str = "DASHSCOPE_API_BASE";
+pub const ENV_WORKSPACE: &str = "DASHSCOPE_WORKSPACE";
+pub const ENV_REGION: &str = "DASHSCOPE_REGION";
+pub const ENV_WS_URL: &str = "DASHSCOPE_WS_URL";
+pub const ENV_PROXY: &str = "DASHSCOPE_PROXY";
+/// 请求超时时间，单位为秒
+pub const ENV_TIMEOUT: &str = "DASHSCOPE_TIMEOUT";
+/// 配置文件路径，默认为 `~/.dashscope/config.toml`
+pub const ENV_CONFIG_FILE: &str = "DASHSCOPE_CONFIG_FILE";
+/// 默认使用的 profile 名称
+pub const ENV_PROFILE: &str = "DASHSCOPE_PROFILE";
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// 配置文件中的一个 profile，所有字段均可省略
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct Profile {
+    pub api_key: Option<String>,
+    /// 保存 API Key 的文件路径，设置后优先于 `api_key`，文件内容变化后自动生效
+    pub api_key_file: Option<String>,
+    pub api_base: Option<String>,
+    /// 地域，例如 `cn-beijing`、`ap-southeast-1`
+    pub region: Option<String>,
+    /// WebSocket 地址，默认由地域推导
+    pub ws_url: Option<String>,
+    pub workspace: Option<String>,
+    pub proxy: Option<String>,
+    /// 请求超时时间，单位为秒
+    pub timeout: Option<u64>,
+}
+
+impl Profile {
+    /// 从环境变量读取
+    pub fn from_env() -> Result<Self> {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let timeout = var(ENV_TIMEOUT)
+            .map(|v| {
+                v.parse::<u64>().map_err(|_| {
+                    DashScopeError::ConfigError(format!("{ENV_TIMEOUT} must be seconds, got {v}"))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            api_key: var(ENV_API_KEY),
+            api_key_file: var(ENV_API_KEY_FILE),
+            api_base: var(ENV_API_BASE),
+            region: var(ENV_REGION),
+            ws_url: var(ENV_WS_URL),
+            workspace: var(ENV_WORKSPACE),
+            proxy: var(ENV_PROXY),
+            timeout,
+        })
+    }
+
+    /// 从配置文件中读取指定的 profile
+    pub fn from_file(path: impl AsRef<Path>, profile: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            DashScopeError::ConfigError(format!("failed to read {}: {e}", path.display()))
+        })?;
+
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let mut profiles: HashMap<String, Profile> = if is_json {
+            serde_json::from_str(&content).map_err(|e| {
+                DashScopeError::ConfigError(format!("invalid config file {}: {e}", path.display()))
+            })?
+        } else {
+            toml::from_str(&content).map_err(|e| {
+                DashScopeError::ConfigError(format!("invalid config file {}: {e}", path.display()))
+            })?
+        };
+
+        profiles.remove(profile).ok_or_else(|| {
+            DashScopeError::ConfigError(format!(
+                "profile `{profile}` not found in {}",
+                path.display()
+            ))
+        })
+    }
+
+    /// 用 `other` 中已设置的字段覆盖当前字段
+    pub fn merge(mut self, other: Profile) -> Self {
+        self.api_key = other.api_key.or(self.api_key);
+        self.api_key_file = other.api_key_file.or(self.api_key_file);
+        self.api_base = other.api_base.or(self.api_base);
+        self.region = other.region.or(self.region);
+        self.ws_url = other.ws_url.or(self.ws_url);
+        self.workspace = other.workspace.or(self.workspace);
+        self.proxy = other.proxy.or(self.proxy);
+        self.timeout = other.timeout.or(self.timeout);
+        self
+    }
+
+    pub fn into_config(self) -> Result<Config> {
+        let mut builder = ConfigBuilder::default();
+        builder.api_key(self.api_key.unwrap_or_default());
+        if let Some(path) = self.api_key_file {
+            builder.credential_provider(FileCredential::new(path)?);
+        }
+
+        if let Some(region) = self.region {
+            builder.region(region.parse::<Region>()?);
+        }
+        if let Some(api_base) = self.api_base {
+            builder.api_base(api_base);
+        }
+        if let Some(ws_url) = self.ws_url {
+            builder.ws_url(ws_url);
+        }
+        if let Some(workspace) = self.workspace {
+            builder.workspace(workspace);
+        }
+        if let Some(proxy) = self.proxy {
+            builder.proxy(proxy);
+        }
+        if let Some(timeout) = self.timeout {
+            builder.timeout(Duration::from_secs(timeout));
+        }
+
+        builder
+            .build()
+            .map_err(|e| DashScopeError::ConfigError(e.to_string()))
+    }
+}
+
+/// 默认配置文件路径：`$DASHSCOPE_CONFIG_FILE` 或 `~/.dashscope/config.toml`
+pub fn default_config_file() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(ENV_CONFIG_FILE) {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".dashscope").join("config.toml"))
+}
+
+impl Config {
+    /// 从环境变量加载配置
+    ///
+    /// 支持 `DASHSCOPE_API_KEY`、`DASHSCOPE_API_BASE`、`DASHSCOPE_WORKSPACE`、
+    /// `DASHSCOPE_REGION`、`DASHSCOPE_WS_URL`、`DASHSCOPE_PROXY` 以及 `DASHSCOPE_TIMEOUT`（秒）。
+    pub fn from_env() -> Result<Self> {
+        Profile::from_env()?.into_config()
+    }
+
+    /// 从配置文件加载指定的 profile，profile 中未设置 `api_key` 时使用 `DASHSCOPE_API_KEY`
+    pub fn from_file(path: impl AsRef<Path>, profile: &str) -> Result<Self> {
+        let env_key = Profile {
+            api_key: std::env::var(ENV_API_KEY).ok(),
+            ..Default::default()
+        };
+        env_key
+            .merge(Profile::from_file(path, profile)?)
+            .into_config()
+    }
+
+    /// 从默认配置文件加载 profile
+    ///
+    /// `profile` 为 `None` 时依次使用 `DASHSCOPE_PROFILE` 和 `default`。
+    pub fn from_profile(profile: Option<&str>) -> Result<Self> {
+        let path = default_config_file().ok_or_else(|| {
+            DashScopeError::ConfigError("cannot determine config file path".into())
+        })?;
+        let profile = match profile {
+            Some(profile) => profile.to_string(),
+            None => std::env::var(ENV_PROFILE).unwrap_or_else(|_| DEFAULT_PROFILE.to_string()),
+        };
+        Self::from_file(path, &profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret as _;
+
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("async-dashscope-{}-{name}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_from_toml_file() {
+        let path = write_temp(
+            "config.toml",
+            r#"
+[default]
+api_key = "sk-default"
+
+[intl]
+api_key = "sk-intl"
+region = "ap-southeast-1"
+workspace = "ws-1"
+timeout = 30
+"#,
+        );
+
+        let config = Config::from_file(&path, "intl").unwrap();
+        assert_eq!(config.api_key().expose_secret(), "sk-intl");
+        assert_eq!(config.region(), Region::ApSoutheast1);
+        assert_eq!(config.workspace(), Some("ws-1"));
+        assert_eq!(config.timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(config.headers()["X-DashScope-WorkSpace"], "ws-1");
+
+        assert!(matches!(
+            Config::from_file(&path, "missing"),
+            Err(DashScopeError::ConfigError(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_from_json_file() {
+        let path = write_temp(
+            "config.json",
+            r#"{"staging": {"api_key": "sk-staging", "api_base": "http://localhost:8080"}}"#,
+        );
+        let config = Config::from_file(&path, "staging").unwrap();
+        assert_eq!(config.url("/files"), "http://localhost:8080/files");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_region() {
+        let profile = Profile {
+            region: Some("mars-1".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            profile.into_config(),
+            Err(DashScopeError::ConfigError(_))
+        ));
+    }
+}
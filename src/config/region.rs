@@ -0,0 +1,155 @@
+//! 服务地域
+//!
+//! HTTP、WebSocket 和文件上传的地址都由地域推导，保证三者始终指向同一个部署：
+//!
+//! ```rust
+//! use async_dashscope::config::{ConfigBuilder, Region};
+//!
+//! let config = ConfigBuilder::default()
+//!     .region(Region::ApSoutheast1)
+//!     .api_key("test")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(
+//!     config.ws_url(),
+//!     "wss://dashscope-intl.aliyuncs.com/api-ws/v1/inference"
+//! );
+//! ```
+use std::{fmt::Display, str::FromStr};
+
+use super::{DASHSCOPE_API_BASE, DASHSCOPE_INTL_API_BASE};
+use crate::error::DashScopeError;
+
+/// 华北2（北京）地域的 WebSocket 地址
+pub const DASHSCOPE_WS_URL: &str = "wss://dashscope.aliyuncs.com/api-ws/v1/inference";
+
+/// 新加坡地域（国际版）的 WebSocket 地址
+pub const DASHSCOPE_INTL_WS_URL: &str = "wss://dashscope-intl.aliyuncs.com/api-ws/v1/inference";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Region {
+    /// 华北2（北京）
+    #[default]
+    CnBeijing,
+    /// 新加坡（国际版）
+    ApSoutheast1,
+    /// 自定义地址，例如私有化部署或本地 mock 服务
+    ///
+    /// `ws_url` 为空时由 `api_base` 推导：`http` 换成 `ws`，`/api/v1` 换成 `/api-ws/v1/inference`。
+    Custom {
+        api_base: String,
+        ws_url: Option<String>,
+    },
+}
+
+impl Region {
+    /// 仅指定 HTTP 地址的自定义地域
+    pub fn custom(api_base: impl Into<String>) -> Self {
+        Self::Custom {
+            api_base: api_base.into(),
+            ws_url: None,
+        }
+    }
+
+    /// 根据 HTTP 地址识别地域，无法识别时为 [`Region::Custom`]
+    pub fn from_api_base(api_base: &str) -> Self {
+        match api_base.trim_end_matches('/') {
+            DASHSCOPE_API_BASE => Self::CnBeijing,
+            DASHSCOPE_INTL_API_BASE => Self::ApSoutheast1,
+            _ => Self::custom(api_base),
+        }
+    }
+
+    pub fn api_base(&self) -> &str {
+        match self {
+            Self::CnBeijing => DASHSCOPE_API_BASE,
+            Self::ApSoutheast1 => DASHSCOPE_INTL_API_BASE,
+            Self::Custom { api_base, .. } => api_base,
+        }
+    }
+
+    pub fn ws_url(&self) -> String {
+        match self {
+            Self::CnBeijing => DASHSCOPE_WS_URL.to_string(),
+            Self::ApSoutheast1 => DASHSCOPE_INTL_WS_URL.to_string(),
+            Self::Custom {
+                ws_url: Some(ws_url),
+                ..
+            } => ws_url.clone(),
+            Self::Custom { api_base, .. } => derive_ws_url(api_base),
+        }
+    }
+}
+
+fn derive_ws_url(api_base: &str) -> String {
+    let base = api_base.trim_end_matches('/');
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        base.to_string()
+    };
+
+    match base.strip_suffix("/api/v1") {
+        Some(host) => format!("{host}/api-ws/v1/inference"),
+        None => format!("{base}/inference"),
+    }
+}
+
+impl FromStr for Region {
+    type Err = DashScopeError;
+
+    /// 支持 `cn-beijing`、`ap-southeast-1`（别名 `intl`、`singapore`）以及 `http(s)://` 开头的自定义地址
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cn-beijing" | "beijing" | "cn" => Ok(Self::CnBeijing),
+            "ap-southeast-1" | "singapore" | "intl" => Ok(Self::ApSoutheast1),
+            _ if s.starts_with("http://") || s.starts_with("https://") => Ok(Self::custom(s)),
+            _ => Err(DashScopeError::ConfigError(format!("unknown region: {s}"))),
+        }
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CnBeijing => write!(f, "cn-beijing"),
+            Self::ApSoutheast1 => write!(f, "ap-southeast-1"),
+            Self::Custom { api_base, .. } => write!(f, "{api_base}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("cn-beijing".parse::<Region>().unwrap(), Region::CnBeijing);
+        assert_eq!("intl".parse::<Region>().unwrap(), Region::ApSoutheast1);
+        assert_eq!(
+            "http://localhost:8080".parse::<Region>().unwrap(),
+            Region::custom("http://localhost:8080")
+        );
+        assert!("mars-1".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn test_ws_url() {
+        assert_eq!(Region::CnBeijing.ws_url(), DASHSCOPE_WS_URL);
+        assert_eq!(
+            Region::from_api_base(DASHSCOPE_INTL_API_BASE).ws_url(),
+            DASHSCOPE_INTL_WS_URL
+        );
+        assert_eq!(
+            Region::custom("http://localhost:8080").ws_url(),
+            "ws://localhost:8080/inference"
+        );
+        assert_eq!(
+            Region::custom("https://proxy.example.com/api/v1/").ws_url(),
+            "wss://proxy.example.com/api-ws/v1/inference"
+        );
+    }
+}
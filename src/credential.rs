@@ -0,0 +1,307 @@
+//! API Key 提供者
+//!
+//! [`Config`](crate::config::Config) 在构造每个请求时都会向 [`CredentialProvider`] 获取 API Key，
+//! 因此可以在不重启服务的情况下轮换密钥，或者把请求分摊到多个子账号的密钥上。
+//!
+//! ```rust
+//! use async_dashscope::{
+//!     Client,
+//!     config::ConfigBuilder,
+//!     credential::{KeyPool, PoolStrategy},
+//! };
+//!
+//! let pool = KeyPool::new(["sk-a", "sk-b", "sk-c"], PoolStrategy::LeastThrottled).unwrap();
+//! let config = ConfigBuilder::default()
+//!     .credential_provider(pool)
+//!     .build()
+//!     .unwrap();
+//! let client = Client::with_config(config);
+//! ```
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use secrecy::{ExposeSecret as _, SecretString};
+
+use crate::error::{DashScopeError, Result};
+
+/// API Key 提供者
+pub trait CredentialProvider: Send + Sync + Debug {
+    /// 返回本次请求使用的 API Key
+    fn api_key(&self) -> SecretString;
+
+    /// 使用 `api_key` 的请求被限流（HTTP 429 或 `Throttling` 错误码）时调用
+    fn on_throttled(&self, _api_key: &str) {}
+}
+
+/// 固定的 API Key
+#[derive(Debug, Clone)]
+pub struct StaticCredential(SecretString);
+
+impl StaticCredential {
+    pub fn new(api_key: impl Into<SecretString>) -> Self {
+        Self(api_key.into())
+    }
+}
+
+impl CredentialProvider for StaticCredential {
+    fn api_key(&self) -> SecretString {
+        self.0.clone()
+    }
+}
+
+/// 每次请求时从环境变量读取 API Key，变量不存在时返回空字符串
+#[derive(Debug, Clone)]
+pub struct EnvCredential {
+    var: String,
+}
+
+impl EnvCredential {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl Default for EnvCredential {
+    fn default() -> Self {
+        Self::new("DASHSCOPE_API_KEY")
+    }
+}
+
+impl CredentialProvider for EnvCredential {
+    fn api_key(&self) -> SecretString {
+        std::env::var(&self.var).unwrap_or_default().into()
+    }
+}
+
+/// 从文件读取 API Key，文件修改时间变化后自动重新加载
+///
+/// 文件内容首尾的空白会被去掉。重新加载失败时继续使用上一次读取到的密钥。
+#[derive(Debug)]
+pub struct FileCredential {
+    path: PathBuf,
+    cache: Mutex<(Option<SystemTime>, SecretString)>,
+}
+
+impl FileCredential {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        let api_key = read_key(&path)?;
+        Ok(Self {
+            path,
+            cache: Mutex::new((modified, api_key)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_key(path: &Path) -> Result<SecretString> {
+    std::fs::read_to_string(path)
+        .map(|s| s.trim().to_string().into())
+        .map_err(|e| {
+            DashScopeError::ConfigError(format!("failed to read {}: {e}", path.display()))
+        })
+}
+
+impl CredentialProvider for FileCredential {
+    fn api_key(&self) -> SecretString {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let modified = modified_time(&self.path);
+        if modified != cache.0 {
+            match read_key(&self.path) {
+                Ok(api_key) => *cache = (modified, api_key),
+                Err(e) => tracing::warn!("keep previous api key: {e}"),
+            }
+        }
+        cache.1.clone()
+    }
+}
+
+/// 密钥池的选择策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolStrategy {
+    /// 依次轮流使用
+    #[default]
+    RoundRobin,
+    /// 优先使用最近一段时间内被限流次数最少的密钥，次数相同时轮流使用
+    LeastThrottled,
+}
+
+/// 限流次数的统计窗口
+const THROTTLE_WINDOW: Duration = Duration::from_secs(60);
+
+/// 多个 API Key 组成的密钥池
+pub struct KeyPool {
+    keys: Vec<SecretString>,
+    strategy: PoolStrategy,
+    next: AtomicUsize,
+    throttled: Mutex<Vec<VecDeque<Instant>>>,
+}
+
+impl Debug for KeyPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyPool")
+            .field("keys", &self.keys.len())
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+impl KeyPool {
+    /// 创建密钥池，`keys` 为空时返回 [`DashScopeError::InvalidArgument`]
+    pub fn new<I, K>(keys: I, strategy: PoolStrategy) -> Result<Self>
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<SecretString>,
+    {
+        let keys: Vec<SecretString> = keys.into_iter().map(Into::into).collect();
+        if keys.is_empty() {
+            return Err(DashScopeError::InvalidArgument(
+                "key pool requires at least one API key".into(),
+            ));
+        }
+        let throttled = vec![VecDeque::new(); keys.len()];
+        Ok(Self {
+            keys,
+            strategy,
+            next: AtomicUsize::new(0),
+            throttled: Mutex::new(throttled),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn strategy(&self) -> PoolStrategy {
+        self.strategy
+    }
+
+    /// 各密钥在统计窗口内被限流的次数，顺序与构造时一致
+    pub fn throttle_counts(&self) -> Vec<usize> {
+        let mut throttled = self.throttled.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        throttled
+            .iter_mut()
+            .map(|hits| {
+                prune(hits, now);
+                hits.len()
+            })
+            .collect()
+    }
+
+    fn select(&self) -> usize {
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.keys.len();
+        match self.strategy {
+            PoolStrategy::RoundRobin => start,
+            PoolStrategy::LeastThrottled => {
+                let counts = self.throttle_counts();
+                (0..self.keys.len())
+                    .map(|offset| (start + offset) % self.keys.len())
+                    .min_by_key(|&i| counts[i])
+                    .unwrap_or(start)
+            }
+        }
+    }
+}
+
+fn prune(hits: &mut VecDeque<Instant>, now: Instant) {
+    while hits
+        .front()
+        .is_some_and(|t| now.duration_since(*t) > THROTTLE_WINDOW)
+    {
+        hits.pop_front();
+    }
+}
+
+impl CredentialProvider for KeyPool {
+    fn api_key(&self) -> SecretString {
+        self.keys[self.select()].clone()
+    }
+
+    fn on_throttled(&self, api_key: &str) {
+        let Some(index) = self.keys.iter().position(|k| k.expose_secret() == api_key) else {
+            return;
+        };
+        let mut throttled = self.throttled.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        prune(&mut throttled[index], now);
+        throttled[index].push_back(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(provider: &dyn CredentialProvider) -> String {
+        provider.api_key().expose_secret().to_string()
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let pool = KeyPool::new(["a", "b", "c"], PoolStrategy::RoundRobin).unwrap();
+        let keys: Vec<String> = (0..4).map(|_| next(&pool)).collect();
+        assert_eq!(keys, ["a", "b", "c", "a"]);
+
+        let empty = KeyPool::new(Vec::<String>::new(), PoolStrategy::RoundRobin);
+        assert!(matches!(empty, Err(DashScopeError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_least_throttled() {
+        let pool = KeyPool::new(["a", "b"], PoolStrategy::LeastThrottled).unwrap();
+        pool.on_throttled("a");
+        pool.on_throttled("unknown");
+        assert_eq!(pool.throttle_counts(), [1, 0]);
+        assert!((0..4).all(|_| next(&pool) == "b"));
+
+        pool.on_throttled("b");
+        pool.on_throttled("b");
+        assert_eq!(next(&pool), "a");
+    }
+
+    #[test]
+    fn test_file_reload() {
+        let path = std::env::temp_dir().join(format!(
+            "async-dashscope-{}-api-key",
+            std::process::id()
+        ));
+        std::fs::write(&path, "sk-old\n").unwrap();
+        let provider = FileCredential::new(&path).unwrap();
+        assert_eq!(next(&provider), "sk-old");
+
+        std::fs::write(&path, "sk-new").unwrap();
+        // 保证修改时间发生变化
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(next(&provider), "sk-new");
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(next(&provider), "sk-new");
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use reqwest::StatusCode;
 use reqwest_eventsource::CannotCloneRequestError;
 use serde::Deserialize;
 
@@ -26,6 +27,19 @@ pub enum DashScopeError {
     InvalidArgument(String),
     #[error("stream error:{0}")]
     StreamError(String),
+    /// 流式请求在收到任何数据之前失败
+    #[error("stream failed before receiving any event after {attempts} attempt(s): {source}")]
+    StreamConnect {
+        source: Box<DashScopeError>,
+        attempts: u32,
+    },
+    /// 流式请求在收到部分数据后中断，`partial_output` 为已接收的文本
+    #[error("stream interrupted after {received_events} event(s): {source}")]
+    StreamInterrupted {
+        source: Box<DashScopeError>,
+        received_events: usize,
+        partial_output: String,
+    },
     #[error("response body contains invalid UTF-8: {0}")]
     InvalidUtf8(#[from] std::string::FromUtf8Error),
 
@@ -34,9 +48,36 @@ pub enum DashScopeError {
     
     #[error("timeout error: {0}")]
     TimeoutError(String),
+
+    #[error("request cancelled")]
+    Cancelled,
+
+    #[error("config error: {0}")]
+    ConfigError(String),
+
+    /// 模型输出无法解析为期望的结构，`raw_output` 为最后一次的输出
+    #[error("failed to parse structured output after {attempts} attempt(s): {source}")]
+    StructuredOutput {
+        source: serde_json::Error,
+        raw_output: String,
+        attempts: u32,
+    },
+
+    /// 截断历史消息后输入仍超过模型的上下文长度
+    #[error("input of {tokens} tokens exceeds the context limit of {limit} tokens for model {model}")]
+    ContextLengthExceeded {
+        model: String,
+        tokens: usize,
+        limit: usize,
+    },
+
+    #[error(transparent)]
+    ToolError(#[from] crate::operation::tool::ToolError),
+
+    /// 装箱以免增大 `DashScopeError` 的体积
     #[cfg(feature = "websocket")]
     #[error("websocket error: {0}")]
-    WebSocketError(#[from] reqwest_websocket::Error),
+    WebSocketError(Box<reqwest_websocket::Error>),
 
     #[error("unknown event type: {event_type}")]
     UnknownEventType{
@@ -44,23 +85,264 @@ pub enum DashScopeError {
     }
 }
 
+#[cfg(feature = "websocket")]
+impl From<reqwest_websocket::Error> for DashScopeError {
+    fn from(value: reqwest_websocket::Error) -> Self {
+        Self::WebSocketError(Box::new(value))
+    }
+}
+
+/// DashScope 返回的错误
+///
+/// 后续可能增加字段，crate 外请使用 [`ApiError::new`] 及 `with_*` 方法构造。
 #[derive(Debug, Deserialize, Clone)]
+#[non_exhaustive]
 pub struct ApiError {
     pub message: String,
     pub request_id: Option<String>,
     pub code: Option<String>,
+    /// 响应的 HTTP 状态码，由客户端在收到响应后填充，通过 [`ApiError::status`] 读取
+    #[serde(skip)]
+    pub(crate) status: Option<StatusCode>,
 }
 
 impl Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut parts = Vec::new();
+        if let Some(status) = &self.status {
+            parts.push(format!("status: {}", status.as_u16()));
+        }
         if let Some(code) = &self.code {
             parts.push(format!("code: {code}"));
         }
         if let Some(request_id) = &self.request_id {
             parts.push(format!("request_id: {request_id}"));
         }
-        write!(f, "{}", parts.join(" "))
+        if parts.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} ({})", self.message, parts.join(", "))
+        }
+    }
+}
+
+impl ApiError {
+    /// 只包含错误信息的 `ApiError`，其余字段为空
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            request_id: None,
+            code: None,
+            status: None,
+        }
+    }
+
+    /// 设置错误码
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// 设置请求 ID
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// 设置 HTTP 状态码
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// 从非 2xx 响应构建错误，响应体不是 JSON 时（例如网关返回的 5xx 页面）使用原始内容作为 message
+    pub(crate) fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        serde_json::from_slice::<ApiError>(body)
+            .unwrap_or_else(|_| ApiError::new(String::from_utf8_lossy(body)))
+            .with_status(status)
+    }
+
+    /// 从 SSE 事件中识别错误载荷：`event: error`，或者只有 `code`/`message` 而没有 `output` 的数据
+    pub(crate) fn from_stream_event(event: &str, value: &serde_json::Value) -> Option<Self> {
+        let is_error_shaped = value.get("output").is_none()
+            && value.get("code").is_some_and(|c| !c.is_null())
+            && value.get("message").is_some();
+        if event != "error" && !is_error_shaped {
+            return None;
+        }
+
+        let str_field = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Some(ApiError {
+            message: str_field("message").unwrap_or_else(|| value.to_string()),
+            request_id: str_field("request_id"),
+            code: str_field("code"),
+            status: value
+                .get("status_code")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| StatusCode::from_u16(v as u16).ok()),
+        })
+    }
+
+    /// 响应的 HTTP 状态码，流式事件中的错误可能没有状态码
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// 将 `code` 解析为 [`ApiErrorCode`]
+    pub fn error_code(&self) -> Option<ApiErrorCode> {
+        let code = ApiErrorCode::from(self.code.as_deref()?);
+        // 模型不存在时 DashScope 返回 InvalidParameter，需要结合 message 判断
+        if code == ApiErrorCode::InvalidParameter
+            && self.message.to_lowercase().contains("model not exist")
+        {
+            return Some(ApiErrorCode::ModelNotFound);
+        }
+        Some(code)
+    }
+
+    /// 是否为可重试的错误：限流、服务端错误或超时
+    pub fn is_retryable(&self) -> bool {
+        if let Some(status) = self.status {
+            if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                return true;
+            }
+        }
+        matches!(
+            self.error_code(),
+            Some(
+                ApiErrorCode::Throttling
+                    | ApiErrorCode::InternalError
+                    | ApiErrorCode::ServiceUnavailable
+                    | ApiErrorCode::RequestTimeOut
+            )
+        )
+    }
+
+    /// 是否为认证或鉴权错误
+    pub fn is_auth_error(&self) -> bool {
+        if let Some(status) = self.status {
+            if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                return true;
+            }
+        }
+        matches!(
+            self.error_code(),
+            Some(ApiErrorCode::InvalidApiKey | ApiErrorCode::AccessDenied)
+        )
+    }
+
+    /// 是否因内容安全审核未通过而失败
+    pub fn is_content_filtered(&self) -> bool {
+        self.error_code() == Some(ApiErrorCode::DataInspectionFailed)
+    }
+
+    /// 是否因账户欠费而失败
+    pub fn is_arrearage(&self) -> bool {
+        self.error_code() == Some(ApiErrorCode::Arrearage)
+    }
+
+    /// 是否因限流而失败
+    pub fn is_throttling(&self) -> bool {
+        self.status == Some(StatusCode::TOO_MANY_REQUESTS)
+            || self.error_code() == Some(ApiErrorCode::Throttling)
+    }
+}
+
+/// DashScope 错误码
+///
+/// 带子类型的错误码（如 `Throttling.RateQuota`）按 `.` 之前的部分归类，
+/// 原始错误码仍保留在 [`ApiError::code`] 中。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiErrorCode {
+    /// API Key 无效
+    InvalidApiKey,
+    /// 无权访问，例如模型未开通
+    AccessDenied,
+    /// 请求频率或配额超限
+    Throttling,
+    /// 输入或输出内容未通过内容安全审核
+    DataInspectionFailed,
+    /// 账户欠费
+    Arrearage,
+    /// 请求参数错误
+    InvalidParameter,
+    /// 模型不存在
+    ModelNotFound,
+    /// 服务内部错误
+    InternalError,
+    /// 服务暂不可用
+    ServiceUnavailable,
+    /// 请求超时
+    RequestTimeOut,
+    /// 其他错误码
+    Other(String),
+}
+
+impl From<&str> for ApiErrorCode {
+    fn from(code: &str) -> Self {
+        let (head, _) = code.split_once('.').unwrap_or((code, ""));
+        match head {
+            "InvalidApiKey" => Self::InvalidApiKey,
+            "AccessDenied" => Self::AccessDenied,
+            "Model" if code.starts_with("Model.AccessDenied") => Self::AccessDenied,
+            "Throttling" => Self::Throttling,
+            "DataInspectionFailed" | "data_inspection_failed" => Self::DataInspectionFailed,
+            "Arrearage" => Self::Arrearage,
+            "InvalidParameter" => Self::InvalidParameter,
+            "ModelNotFound" | "ModelNotExist" | "model_not_found" => Self::ModelNotFound,
+            "InternalError" | "SystemError" => Self::InternalError,
+            "ServiceUnavailable" => Self::ServiceUnavailable,
+            "RequestTimeOut" => Self::RequestTimeOut,
+            _ => Self::Other(code.to_string()),
+        }
+    }
+}
+
+/// 是否为可以重试的网络错误：超时、连接失败，以及发送请求或读取响应体时连接中断
+///
+/// [`DashScopeError::is_retryable`] 与 [`RetryPolicy`](crate::retry::RetryPolicy) 共用这一判断。
+pub(crate) fn is_transient_network_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+impl DashScopeError {
+    /// 返回内部的 [`ApiError`]，流式错误会返回其包装的 API 错误
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            DashScopeError::ApiError(e) => Some(e),
+            DashScopeError::StreamConnect { source, .. }
+            | DashScopeError::StreamInterrupted { source, .. } => source.api_error(),
+            _ => None,
+        }
+    }
+
+    /// 是否为可重试的错误，包括限流、服务端错误以及超时、连接失败等网络错误
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DashScopeError::Reqwest(e) => is_transient_network_error(e),
+            DashScopeError::TimeoutError(_) => true,
+            DashScopeError::StreamConnect { source, .. }
+            | DashScopeError::StreamInterrupted { source, .. } => source.is_retryable(),
+            _ => self.api_error().is_some_and(ApiError::is_retryable),
+        }
+    }
+
+    /// 是否为认证或鉴权错误
+    pub fn is_auth_error(&self) -> bool {
+        self.api_error().is_some_and(ApiError::is_auth_error)
+    }
+
+    /// 是否因内容安全审核未通过而失败
+    pub fn is_content_filtered(&self) -> bool {
+        self.api_error().is_some_and(ApiError::is_content_filtered)
+    }
+
+    /// 返回 HTTP 状态码（如果有）
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            DashScopeError::Reqwest(e) => e.status(),
+            _ => self.api_error().and_then(ApiError::status),
+        }
     }
 }
 
@@ -70,6 +352,12 @@ impl From<crate::operation::common::ParametersBuilderError> for DashScopeError {
     }
 }
 
+impl From<crate::operation::generation::param::MessageBuilderError> for DashScopeError {
+    fn from(error: crate::operation::generation::param::MessageBuilderError) -> Self {
+        DashScopeError::InvalidArgument(error.to_string())
+    }
+}
+
 pub(crate) fn map_deserialization_error(e: serde_json::Error, bytes: &[u8]) -> DashScopeError {
     tracing::error!(
         "failed deserialization of: {}",
@@ -81,4 +369,86 @@ pub(crate) fn map_deserialization_error(e: serde_json::Error, bytes: &[u8]) -> D
     }
 }
 
-pub type Result<T> = std::result::Result<T, DashScopeError>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, DashScopeError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(status: u16, code: &str, message: &str) -> ApiError {
+        ApiError::new(message)
+            .with_request_id("req-1")
+            .with_code(code)
+            .with_status(StatusCode::from_u16(status).unwrap())
+    }
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(
+            ApiErrorCode::from("Throttling.RateQuota"),
+            ApiErrorCode::Throttling
+        );
+        assert_eq!(
+            ApiErrorCode::from("Model.AccessDenied"),
+            ApiErrorCode::AccessDenied
+        );
+        assert_eq!(
+            ApiErrorCode::from("Foo"),
+            ApiErrorCode::Other("Foo".to_string())
+        );
+        assert_eq!(
+            api_error(400, "InvalidParameter", "Model not exist.").error_code(),
+            Some(ApiErrorCode::ModelNotFound)
+        );
+    }
+
+    #[test]
+    fn test_predicates() {
+        let e = DashScopeError::ApiError(api_error(429, "Throttling.RateQuota", "too many"));
+        assert!(e.is_retryable());
+        assert!(!e.is_auth_error());
+
+        let e = DashScopeError::ApiError(api_error(401, "InvalidApiKey", "bad key"));
+        assert!(e.is_auth_error());
+        assert!(!e.is_retryable());
+
+        let e = DashScopeError::StreamInterrupted {
+            source: Box::new(DashScopeError::ApiError(api_error(
+                400,
+                "DataInspectionFailed",
+                "inappropriate content",
+            ))),
+            received_events: 3,
+            partial_output: String::new(),
+        };
+        assert!(e.is_content_filtered());
+        assert_eq!(e.status(), Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_from_stream_event() {
+        let value = serde_json::json!({
+            "code": "DataInspectionFailed",
+            "message": "Output data may contain inappropriate content.",
+            "request_id": "req-2",
+            "status_code": 400
+        });
+        let e = ApiError::from_stream_event("message", &value).unwrap();
+        assert!(e.is_content_filtered());
+        assert_eq!(e.request_id.as_deref(), Some("req-2"));
+        assert_eq!(e.status(), Some(StatusCode::BAD_REQUEST));
+
+        let chunk = serde_json::json!({"output": {"text": "hi"}, "request_id": "req-3"});
+        assert!(ApiError::from_stream_event("message", &chunk).is_none());
+        assert!(ApiError::from_stream_event("error", &chunk).is_some());
+    }
+
+    #[test]
+    fn test_display() {
+        let e = api_error(401, "InvalidApiKey", "Invalid API-key provided.");
+        assert_eq!(
+            e.to_string(),
+            "Invalid API-key provided. (status: 401, code: InvalidApiKey, request_id: req-1)"
+        );
+    }
+}
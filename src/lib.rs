@@ -1,9 +1,18 @@
 #![doc = include_str!("../docs/base.md")]
 
+extern crate self as async_dashscope;
+
 mod client;
 pub mod config;
+pub mod credential;
 pub mod error;
+pub mod middleware;
 pub mod operation;
+pub mod rate_limit;
+pub mod retry;
+pub mod schema;
+pub mod tokenizer;
+pub mod usage;
 
 pub use client::Client;
 pub(crate) mod oss_util;
\ No newline at end of file
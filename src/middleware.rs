@@ -0,0 +1,160 @@
+//! 请求中间件
+//!
+//! 通过 [`Middleware`] 可以在请求发出前、收到响应后以及出错时插入自定义逻辑，
+//! 例如注入追踪头、审计日志、请求签名或改写响应。中间件注册在 [`Client`](crate::Client) 上，
+//! 对文本生成、多模态、embedding、文件以及任务查询等所有请求统一生效。
+//!
+//! ```rust
+//! use async_dashscope::{Client, error::Result, middleware::Middleware};
+//!
+//! struct TraceHeader;
+//!
+//! impl Middleware for TraceHeader {
+//!     fn before_request(&self, request: &mut reqwest::Request) -> Result<()> {
+//!         request
+//!             .headers_mut()
+//!             .insert("X-Trace-Id", "trace-123".parse().unwrap());
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let client = Client::new().with_middleware(TraceHeader);
+//! ```
+use std::{fmt::Debug, sync::Arc};
+
+use bytes::Bytes;
+use reqwest::{Request, StatusCode, header::HeaderMap};
+
+use crate::error::{DashScopeError, Result};
+
+/// 中间件可见的响应内容
+///
+/// `after_response` 中对 `status`、`headers`、`body` 的修改会影响后续的错误判断和反序列化。
+#[derive(Debug, Clone)]
+pub struct ResponseParts {
+    /// HTTP 状态码
+    pub status: StatusCode,
+    /// 响应头
+    pub headers: HeaderMap,
+    /// 响应体
+    pub body: Bytes,
+    /// 本次响应对应第几次尝试（从 1 开始），大于 1 表示经过了重试
+    pub attempt: u32,
+}
+
+/// 请求中间件
+///
+/// 所有方法都有默认实现，只需覆盖关心的钩子即可。
+///
+/// - `before_request` 按注册顺序调用，返回错误会终止本次请求。请求被重试时每次尝试都会调用，
+///   流式请求在每次建立连接（包括连接阶段的重试）时调用；
+/// - `after_response` 按注册的逆序调用。它需要完整的响应体，流式请求的响应是持续下发的事件，
+///   因此不会触发，流式请求的出错信息只能通过 `on_error` 获得；
+/// - `on_error` 在请求最终失败时按注册顺序调用。
+pub trait Middleware: Send + Sync {
+    /// 请求发出前调用，可以修改请求头、URL 等。
+    fn before_request(&self, _request: &mut Request) -> Result<()> {
+        Ok(())
+    }
+
+    /// 收到响应后调用，可以检查或改写响应内容。
+    fn after_response(&self, _response: &mut ResponseParts) -> Result<()> {
+        Ok(())
+    }
+
+    /// 请求失败时调用。
+    fn on_error(&self, _error: &DashScopeError) {}
+}
+
+/// 已注册的中间件列表，随 `Client` 一起克隆。
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareChain(Vec<Arc<dyn Middleware>>);
+
+impl Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareChain")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl MiddlewareChain {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.0.push(middleware);
+    }
+
+    pub(crate) fn before_request(&self, request: &mut Request) -> Result<()> {
+        for m in self.0.iter() {
+            m.before_request(request)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn after_response(&self, response: &mut ResponseParts) -> Result<()> {
+        for m in self.0.iter().rev() {
+            m.after_response(response)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn on_error(&self, error: &DashScopeError) {
+        for m in self.0.iter() {
+            m.on_error(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    struct Recorder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Recorder {
+        fn before_request(&self, _request: &mut Request) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("before:{}", self.name));
+            Ok(())
+        }
+
+        fn after_response(&self, _response: &mut ResponseParts) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("after:{}", self.name));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_chain_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut chain = MiddlewareChain::default();
+        for name in ["a", "b"] {
+            chain.push(Arc::new(Recorder {
+                name,
+                calls: calls.clone(),
+            }));
+        }
+
+        let mut request = Request::new(
+            reqwest::Method::GET,
+            "http://localhost/".parse().unwrap(),
+        );
+        chain.before_request(&mut request).unwrap();
+
+        let mut response = ResponseParts {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            attempt: 1,
+        };
+        chain.after_response(&mut response).unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["before:a", "before:b", "after:b", "after:a"]
+        );
+    }
+}
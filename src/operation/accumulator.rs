@@ -0,0 +1,597 @@
+//! 流式输出的聚合
+//!
+//! 开启 `incremental_output` 后，流中的每个数据块只包含增量内容。[`StreamAccumulator`]
+//! 把这些增量合并成一个完整的输出：文本与思考内容依次拼接，工具调用的参数片段按 `index`
+//! 拼接，`finish_reason`、联网搜索信息和 `usage` 取最后一次出现的值。请求参数 `n` 大于 1 时，
+//! 各候选回复按 `index` 分别合并，最终结果中的 `choices` 按 `index` 排序。
+//!
+//! 未开启 `incremental_output` 时（`call_stream` 的默认情况），每个数据块都已包含截至目前的完整内容。
+//! [`StreamAccumulator::new`] 发现数据块的文本以已合并的文本开头并继续增长时，会认定这是非增量流，
+//! 此后只保留最新的数据块，因此 [`CollectFinal::collect_final`] 对两种流都能得到正确结果。
+//! 已知是非增量流时，也可以直接使用 [`CollectFinal::collect_final_non_incremental`]。
+//!
+//! ```rust,no_run
+//! # async fn run() -> async_dashscope::error::Result<()> {
+//! use async_dashscope::{
+//!     Client,
+//!     operation::{accumulator::CollectFinal as _, common::ParametersBuilder},
+//! };
+//! # let mut request: async_dashscope::operation::generation::GenerationParam = todo!();
+//!
+//! let client = Client::new();
+//!
+//! // 默认的非增量流
+//! let stream = client.generation().call_stream(request.clone()).await?;
+//! let output = stream.collect_final().await?;
+//! println!("{:?}", output.output.choices);
+//!
+//! // 开启增量输出后，每个数据块只包含新生成的内容
+//! request.parameters = Some(ParametersBuilder::default().incremental_output(true).build()?);
+//! let stream = client.generation().call_stream(request).await?;
+//! let output = stream
+//!     .collect_final_with(|chunk| {
+//!         if let Some(choices) = &chunk.output.choices {
+//!             print!("{}", choices[0].message.content);
+//!         }
+//!     })
+//!     .await?;
+//! println!("\nusage: {:?}", output.usage);
+//! # Ok(())
+//! # }
+//! ```
+use std::future::Future;
+
+use tokio_stream::{Stream, StreamExt as _};
+
+use crate::{
+    error::{DashScopeError, Result},
+    operation::{
+        generation::{self, GenerationOutput},
+        multi_modal_conversation::{self, MultiModalConversationOutput},
+    },
+};
+
+/// 可以把增量数据块合并进来的输出类型
+pub trait Accumulate: Clone {
+    /// 把一个增量数据块合并到当前结果中
+    fn merge(&mut self, chunk: Self);
+
+    /// 数据块作为当前结果保存之前调用，用于整理数据块以便后续合并
+    fn normalize(&mut self) {}
+
+    /// 数据块是否包含 `previous` 的全部文本并有所增长，即来自非增量输出的流
+    fn extends(&self, _previous: &Self) -> bool {
+        false
+    }
+}
+
+/// 流式输出聚合器
+#[derive(Debug, Clone)]
+pub struct StreamAccumulator<T> {
+    current: Option<T>,
+    incremental: bool,
+    chunks: usize,
+}
+
+impl<T: Accumulate> Default for StreamAccumulator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Accumulate> StreamAccumulator<T> {
+    /// 用于 `incremental_output` 为 `true` 的流
+    ///
+    /// 若某个数据块包含之前合并的全部文本并有所增长（参见 [`Accumulate::extends`]），
+    /// 则认定为非增量流，之后与 [`StreamAccumulator::non_incremental`] 的行为相同。
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            incremental: true,
+            chunks: 0,
+        }
+    }
+
+    /// 用于非增量输出的流，每个数据块都包含截至目前的完整内容，只保留最后一个
+    pub fn non_incremental() -> Self {
+        Self {
+            incremental: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn push(&mut self, mut chunk: T) {
+        self.chunks += 1;
+        if let Some(current) = &self.current {
+            if self.incremental && chunk.extends(current) {
+                tracing::debug!(
+                    "chunk repeats accumulated text, treating stream as non-incremental"
+                );
+                self.incremental = false;
+            }
+        }
+        match &mut self.current {
+            Some(current) if self.incremental => current.merge(chunk),
+            current => {
+                chunk.normalize();
+                *current = Some(chunk);
+            }
+        }
+    }
+
+    /// 当前已合并的结果
+    pub fn current(&self) -> Option<&T> {
+        self.current.as_ref()
+    }
+
+    /// 是否按增量流合并数据块
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// 已接收的数据块数量
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    pub fn finish(self) -> Option<T> {
+        self.current
+    }
+}
+
+/// 为流式输出提供 `collect_final` 方法
+pub trait CollectFinal<T>: Stream<Item = Result<T>> + Unpin + Send + Sized
+where
+    T: Accumulate + Send,
+{
+    /// 读取整个流并合并为最终结果，增量流与非增量流均可
+    fn collect_final(self) -> impl Future<Output = Result<T>> + Send {
+        self.collect_final_with(|_| {})
+    }
+
+    /// 读取整个流并合并为最终结果，每收到一个数据块都会先调用 `on_chunk`
+    fn collect_final_with<F>(self, on_chunk: F) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnMut(&T) + Send,
+    {
+        self.collect_with(StreamAccumulator::new(), on_chunk)
+    }
+
+    /// 读取整个非增量流，返回最后一个数据块
+    fn collect_final_non_incremental(self) -> impl Future<Output = Result<T>> + Send {
+        self.collect_with(StreamAccumulator::non_incremental(), |_| {})
+    }
+
+    /// 使用指定的聚合器读取整个流，每收到一个数据块都会先调用 `on_chunk`
+    fn collect_with<F>(
+        mut self,
+        mut accumulator: StreamAccumulator<T>,
+        mut on_chunk: F,
+    ) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnMut(&T) + Send,
+    {
+        async move {
+            while let Some(chunk) = self.next().await {
+                let chunk = chunk?;
+                on_chunk(&chunk);
+                accumulator.push(chunk);
+            }
+            accumulator
+                .finish()
+                .ok_or_else(|| DashScopeError::StreamError("stream ended without any chunk".into()))
+        }
+    }
+}
+
+impl<S, T> CollectFinal<T> for S
+where
+    S: Stream<Item = Result<T>> + Unpin + Send,
+    T: Accumulate + Send,
+{
+}
+
+/// 流式过程中 `finish_reason` 为 `"null"` 字符串，不应覆盖已有的值
+fn merge_finish_reason(current: &mut Option<String>, chunk: Option<String>) {
+    if let Some(reason) = chunk {
+        if current.is_none() || (!reason.is_empty() && reason != "null") {
+            *current = Some(reason);
+        }
+    }
+}
+
+fn append(current: &mut Option<String>, delta: Option<String>) {
+    match (current.as_mut(), delta) {
+        (Some(current), Some(delta)) => current.push_str(&delta),
+        (None, delta) => *current = delta,
+        _ => {}
+    }
+}
+
+/// 所有非空的已有文本都是数据块中对应文本的前缀，且至少有一处在此基础上变长
+///
+/// 数据块中缺失的文本不参与比较；仅与已有文本相同不足以判定，
+/// 以免增量流中恰好重复的片段被误判。
+fn contains_all<'a>(pairs: impl IntoIterator<Item = (Option<&'a str>, Option<&'a str>)>) -> bool {
+    let mut grown = false;
+    for (previous, chunk) in pairs {
+        let (Some(previous), Some(chunk)) = (previous, chunk) else {
+            continue;
+        };
+        if previous.is_empty() {
+            continue;
+        }
+        if !chunk.starts_with(previous) {
+            return false;
+        }
+        grown |= chunk.len() > previous.len();
+    }
+    grown
+}
+
+fn merge_tool_calls(current: &mut Vec<generation::ToolCall>, chunk: Vec<generation::ToolCall>) {
+    for call in chunk {
+        match current.iter_mut().find(|c| c.index == call.index) {
+            Some(existing) => {
+                if !call.id.is_empty() {
+                    existing.id = call.id;
+                }
+                if !call.type_.is_empty() {
+                    existing.type_ = call.type_;
+                }
+                if existing.function.name.is_empty() {
+                    existing.function.name = call.function.name;
+                }
+                append(&mut existing.function.arguments, call.function.arguments);
+            }
+            None => current.push(call),
+        }
+    }
+}
+
+impl Accumulate for GenerationOutput {
+    /// 补齐缺失的 `index` 并按 `index` 排序，`merge` 依赖这一顺序查找候选
+    fn normalize(&mut self) {
+        if let Some(choices) = self.output.choices.as_mut() {
+            for (position, choice) in choices.iter_mut().enumerate() {
+                choice.index.get_or_insert(position as u32);
+            }
+            choices.sort_by_key(|choice| choice.index);
+        }
+    }
+
+    fn extends(&self, previous: &Self) -> bool {
+        let mut pairs = vec![(previous.output.text.as_deref(), self.output.text.as_deref())];
+        let previous_choices = previous.output.choices.as_deref().unwrap_or_default();
+        let choices = self.output.choices.as_deref().unwrap_or_default();
+        for (position, choice) in choices.iter().enumerate() {
+            let index = choice.index.unwrap_or(position as u32);
+            let Some(previous) = previous_choices.iter().find(|c| c.index == Some(index)) else {
+                continue;
+            };
+            pairs.push((
+                Some(previous.message.content.as_str()),
+                Some(choice.message.content.as_str()),
+            ));
+            pairs.push((
+                previous.message.reasoning_content.as_deref(),
+                choice.message.reasoning_content.as_deref(),
+            ));
+        }
+        contains_all(pairs)
+    }
+
+    fn merge(&mut self, chunk: Self) {
+        if chunk.request_id.is_some() {
+            self.request_id = chunk.request_id;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        let output = &mut self.output;
+        append(&mut output.text, chunk.output.text);
+        merge_finish_reason(&mut output.finish_reason, chunk.output.finish_reason);
+        if chunk.output.search_info.is_some() {
+            output.search_info = chunk.output.search_info;
+        }
+
+        let Some(chunk_choices) = chunk.output.choices else {
+            return;
+        };
+        let choices = output.choices.get_or_insert_with(Vec::new);
+        for (position, mut choice) in chunk_choices.into_iter().enumerate() {
+            // 请求参数 `n` 大于 1 时各候选交错下发，按 `index` 归并，缺失时按位置
+            let index = *choice.index.get_or_insert(position as u32);
+            let found = choices.binary_search_by_key(&index, |c| c.index.unwrap_or_default());
+            let current = match found {
+                Ok(found) => &mut choices[found],
+                Err(insert_at) => {
+                    choices.insert(insert_at, choice);
+                    continue;
+                }
+            };
+            merge_finish_reason(&mut current.finish_reason, choice.finish_reason);
+
+            let message = &mut current.message;
+            message.content.push_str(&choice.message.content);
+            append(
+                &mut message.reasoning_content,
+                choice.message.reasoning_content,
+            );
+            if message.role.is_empty() {
+                message.role = choice.message.role;
+            }
+            if choice.message.function_call.is_some() {
+                message.function_call = choice.message.function_call;
+            }
+            if let Some(tool_calls) = choice.message.tool_calls {
+                merge_tool_calls(message.tool_calls.get_or_insert_with(Vec::new), tool_calls);
+            }
+            if let Some(logprobs) = choice.logprobs {
+                current
+                    .logprobs
+                    .get_or_insert_with(Default::default)
+                    .content
+                    .extend(logprobs.content);
+            }
+        }
+    }
+}
+
+fn merge_contents(
+    current: &mut Vec<multi_modal_conversation::Content>,
+    chunk: Vec<multi_modal_conversation::Content>,
+) {
+    for (position, content) in chunk.into_iter().enumerate() {
+        match current.get_mut(position) {
+            Some(existing) => {
+                append(&mut existing.text, content.text);
+                if content.image.is_some() {
+                    existing.image = content.image;
+                }
+            }
+            None => current.push(content),
+        }
+    }
+}
+
+impl Accumulate for MultiModalConversationOutput {
+    /// 补齐缺失的 `index` 并按 `index` 排序，`merge` 依赖这一顺序查找候选
+    fn normalize(&mut self) {
+        let choices = &mut self.output.choices;
+        for (position, choice) in choices.iter_mut().enumerate() {
+            choice.index.get_or_insert(position as u32);
+        }
+        choices.sort_by_key(|choice| choice.index);
+    }
+
+    fn extends(&self, previous: &Self) -> bool {
+        let mut pairs = Vec::new();
+        for (position, choice) in self.output.choices.iter().enumerate() {
+            let index = choice.index.unwrap_or(position as u32);
+            let Some(previous) = previous
+                .output
+                .choices
+                .iter()
+                .find(|c| c.index == Some(index))
+            else {
+                continue;
+            };
+            for (previous, content) in previous.message.content.iter().zip(&choice.message.content)
+            {
+                pairs.push((previous.text.as_deref(), content.text.as_deref()));
+            }
+        }
+        contains_all(pairs)
+    }
+
+    fn merge(&mut self, chunk: Self) {
+        if !chunk.request_id.is_empty() {
+            self.request_id = chunk.request_id;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        let choices = &mut self.output.choices;
+        for (position, mut choice) in chunk.output.choices.into_iter().enumerate() {
+            let index = *choice.index.get_or_insert(position as u32);
+            let found = choices.binary_search_by_key(&index, |c| c.index.unwrap_or_default());
+            let current = match found {
+                Ok(found) => &mut choices[found],
+                Err(insert_at) => {
+                    choices.insert(insert_at, choice);
+                    continue;
+                }
+            };
+            merge_finish_reason(&mut current.finish_reason, choice.finish_reason);
+            if current.message.role.is_empty() {
+                current.message.role = choice.message.role;
+            }
+            merge_contents(&mut current.message.content, choice.message.content);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn chunk(value: serde_json::Value) -> GenerationOutput {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_merge_generation() {
+        let chunks = [
+            json!({"request_id": "r1", "output": {"choices": [{"finish_reason": "null",
+                "message": {"role": "assistant", "content": "", "reasoning_content": "think"}}]}}),
+            json!({"request_id": "r1", "output": {"choices": [{"finish_reason": "null",
+                "message": {"role": "assistant", "content": "Hel", "reasoning_content": "ing"}}]}}),
+            json!({"request_id": "r1", "output": {"choices": [{"finish_reason": "null",
+                "message": {"role": "assistant", "content": "lo", "tool_calls": [
+                    {"index": 0, "id": "call_1", "type": "function",
+                     "function": {"name": "get_weather", "arguments": "{\"city\":"}}]}}]}}),
+            json!({"request_id": "r1", "output": {"choices": [{"finish_reason": "tool_calls",
+                "message": {"role": "assistant", "content": "", "tool_calls": [
+                    {"index": 0, "id": "", "type": "function",
+                     "function": {"arguments": "\"杭州\"}"}}]}}]},
+                "usage": {"input_tokens": 10, "output_tokens": 5, "total_tokens": 15}}),
+        ];
+
+        let mut accumulator = StreamAccumulator::new();
+        for c in chunks {
+            accumulator.push(chunk(c));
+        }
+        assert_eq!(accumulator.chunks(), 4);
+
+        let output = accumulator.finish().unwrap();
+        let choice = &output.output.choices.as_ref().unwrap()[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(choice.message.content, "Hello");
+        assert_eq!(
+            choice.message.reasoning_content.as_deref(),
+            Some("thinking")
+        );
+
+        let calls = choice.message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(
+            calls[0].function.arguments.as_deref(),
+            Some("{\"city\":\"杭州\"}")
+        );
+        assert_eq!(output.usage.unwrap().total(), 15);
+    }
+
+    #[test]
+    fn test_merge_multi_modal() {
+        let chunk = |text: &str, reason: &str| -> MultiModalConversationOutput {
+            serde_json::from_value(json!({"request_id": "r2", "output": {"choices": [
+                {"finish_reason": reason, "message": {"role": "assistant", "content": [{"text": text}]}}
+            ]}}))
+            .unwrap()
+        };
+
+        let mut accumulator = StreamAccumulator::new();
+        accumulator.push(chunk("图中", "null"));
+        accumulator.push(chunk("是一只猫", "stop"));
+        let output = accumulator.finish().unwrap();
+        assert_eq!(
+            output.output.choices[0].message.content[0].text.as_deref(),
+            Some("图中是一只猫")
+        );
+        assert_eq!(
+            output.output.choices[0].finish_reason.as_deref(),
+            Some("stop")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_final() {
+        let chunks = ["a", "b", "c"].map(|text| {
+            Ok(chunk(
+                json!({"output": {"text": text, "finish_reason": "null"}}),
+            ))
+        });
+        let stream: generation::GenerationOutputStream = Box::pin(tokio_stream::iter(chunks));
+
+        let mut seen = 0;
+        let output = stream.collect_final_with(|_| seen += 1).await.unwrap();
+        assert_eq!(seen, 3);
+        assert_eq!(output.output.text.as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_final_non_incremental() {
+        let chunks = ["a", "ab", "abc"].map(|text| {
+            Ok(chunk(
+                json!({"output": {"text": text, "finish_reason": "null"}}),
+            ))
+        });
+        let stream: generation::GenerationOutputStream = Box::pin(tokio_stream::iter(chunks));
+
+        let output = stream.collect_final_non_incremental().await.unwrap();
+        assert_eq!(output.output.text.as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_final_detects_cumulative_chunks() {
+        let chunks = [
+            ("想", ""),
+            ("想好了", ""),
+            ("想好了", "你"),
+            ("想好了", "你好"),
+        ]
+        .map(|(reasoning, content)| {
+            Ok(chunk(
+                json!({"output": {"choices": [{"finish_reason": "null",
+                    "message": {"role": "assistant", "content": content,
+                                "reasoning_content": reasoning}}]}}),
+            ))
+        });
+        let stream: generation::GenerationOutputStream = Box::pin(tokio_stream::iter(chunks));
+
+        let output = stream.collect_final().await.unwrap();
+        let message = &output.choices()[0].message;
+        assert_eq!(message.content, "你好");
+        assert_eq!(message.reasoning_content.as_deref(), Some("想好了"));
+
+        // 增量流中新的文本不以已有文本开头，仍然拼接
+        let mut accumulator = StreamAccumulator::new();
+        for text in ["你", "好", "你好"] {
+            accumulator.push(chunk(json!({"output": {"text": text}})));
+        }
+        assert!(accumulator.is_incremental());
+        assert_eq!(
+            accumulator.finish().unwrap().output.text.as_deref(),
+            Some("你好你好")
+        );
+    }
+
+    #[test]
+    fn test_merge_unordered_choices() {
+        let mut accumulator = StreamAccumulator::new();
+        accumulator.push(chunk(json!({"output": {"choices": [
+            {"index": 1, "finish_reason": "null", "message": {"role": "assistant", "content": "乙"}},
+            {"index": 0, "finish_reason": "null", "message": {"role": "assistant", "content": "甲"}}
+        ]}})));
+        accumulator.push(chunk(json!({"output": {"choices": [
+            {"index": 0, "finish_reason": "stop", "message": {"role": "assistant", "content": "一"}}
+        ]}})));
+
+        let output = accumulator.finish().unwrap();
+        let choices = output.choices();
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[0].message.content, "甲一");
+        assert_eq!(choices[1].message.content, "乙");
+    }
+
+    #[test]
+    fn test_merge_multi_modal_by_index() {
+        let chunk = |choices: serde_json::Value| -> MultiModalConversationOutput {
+            serde_json::from_value(json!({"request_id": "r3", "output": {"choices": choices}}))
+                .unwrap()
+        };
+
+        let mut accumulator = StreamAccumulator::new();
+        accumulator.push(chunk(json!([
+            {"index": 1, "finish_reason": "null", "message": {"role": "assistant", "content": [{"text": "狗"}]}},
+            {"index": 0, "finish_reason": "null", "message": {"role": "assistant", "content": [{"text": "猫"}]}}
+        ])));
+        accumulator.push(chunk(json!([
+            {"index": 1, "finish_reason": "stop", "message": {"role": "assistant", "content": [{"text": "叫"}]}}
+        ])));
+
+        let output = accumulator.finish().unwrap();
+        let choices = &output.output.choices;
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[0].message.content[0].text.as_deref(), Some("猫"));
+        assert_eq!(choices[1].message.content[0].text.as_deref(), Some("狗叫"));
+        assert_eq!(choices[1].finish_reason.as_deref(), Some("stop"));
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::error::Result;
 use crate::Client;
+use crate::operation::request::RequestOptions;
 
 const CUSTOMIZATION_PATH: &str = "/services/audio/asr/customization";
 
@@ -123,6 +124,17 @@ impl Customization {
     }
 
     pub async fn create_vocabulary(&self,target_model: &str,prefix: &str,vocabulary:&[VocabularyDetail]) ->Result<CreateVocabularyResponse> {
+        self.create_vocabulary_with_options(
+            target_model,
+            prefix,
+            vocabulary,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// 使用单次请求选项创建热词表，参见 [`Customization::create_vocabulary`]
+    pub async fn create_vocabulary_with_options(&self,target_model: &str,prefix: &str,vocabulary:&[VocabularyDetail], options: &RequestOptions) ->Result<CreateVocabularyResponse> {
         let param = VocabularyParam {
             model: "speech-biasing".to_string(),
             input: VocabularyInput {
@@ -132,11 +144,20 @@ impl Customization {
                 vocabulary: vocabulary.to_vec(),
             },
         };
-        let resp: CreateVocabularyResponse = self.client.post(CUSTOMIZATION_PATH, param).await?;
+        let resp: CreateVocabularyResponse = self
+            .client
+            .post_with_options(CUSTOMIZATION_PATH, param, self.client.config().headers(), options)
+            .await?;
         Ok(resp)
     }
 
     pub async fn query_vocabulary(&self,vocabulary_id: &str) ->Result<QueryVocabularyResponse> {
+        self.query_vocabulary_with_options(vocabulary_id, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项查询热词表，参见 [`Customization::query_vocabulary`]
+    pub async fn query_vocabulary_with_options(&self,vocabulary_id: &str, options: &RequestOptions) ->Result<QueryVocabularyResponse> {
 
         #[derive(Debug, Clone, Serialize, Deserialize)]
         struct QueryVocabularyParam {
@@ -156,12 +177,26 @@ impl Customization {
                 vocabulary_id: vocabulary_id.to_string(),
             },
         };
-        let resp: QueryVocabularyResponse = self.client.post(CUSTOMIZATION_PATH, param).await?;
+        let resp: QueryVocabularyResponse = self
+            .client
+            .post_with_options(CUSTOMIZATION_PATH, param, self.client.config().headers(), options)
+            .await?;
         Ok(resp)
     }
 
     /// bugs in dashscope ?
     pub async fn list_vocabularies(&self,prefix: Option<&str>,page_index: Option<usize>,page_size: Option<usize>) ->Result<ListVocabulariesResponse> {
+        self.list_vocabularies_with_options(
+            prefix,
+            page_index,
+            page_size,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// 使用单次请求选项列出热词表，参见 [`Customization::list_vocabularies`]
+    pub async fn list_vocabularies_with_options(&self,prefix: Option<&str>,page_index: Option<usize>,page_size: Option<usize>, options: &RequestOptions) ->Result<ListVocabulariesResponse> {
         #[derive(Debug, Clone, Serialize, Deserialize)]
         struct ListVocabulariesParam {
             model: String,
@@ -183,11 +218,20 @@ impl Customization {
                 page_size: page_size.unwrap_or(10),
             },
         };
-        let resp: ListVocabulariesResponse = self.client.post(CUSTOMIZATION_PATH, param).await?;
+        let resp: ListVocabulariesResponse = self
+            .client
+            .post_with_options(CUSTOMIZATION_PATH, param, self.client.config().headers(), options)
+            .await?;
         Ok(resp)
     }
 
     pub async fn update_vocabulary(&self,vocabulary_id: &str,vocabulary:&[VocabularyDetail]) ->Result<UpdateVocabularyResponse> {
+        self.update_vocabulary_with_options(vocabulary_id, vocabulary, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项更新热词表，参见 [`Customization::update_vocabulary`]
+    pub async fn update_vocabulary_with_options(&self,vocabulary_id: &str,vocabulary:&[VocabularyDetail], options: &RequestOptions) ->Result<UpdateVocabularyResponse> {
         #[derive(Debug, Clone, Serialize, Deserialize)]
         struct UpdateVocabularyParam {
             model: String,
@@ -208,11 +252,20 @@ impl Customization {
                 vocabulary: vocabulary.to_vec(),
             },
         };
-        let resp: UpdateVocabularyResponse = self.client.post(CUSTOMIZATION_PATH, param).await?;
+        let resp: UpdateVocabularyResponse = self
+            .client
+            .post_with_options(CUSTOMIZATION_PATH, param, self.client.config().headers(), options)
+            .await?;
         Ok(resp)
     }
 
     pub async fn delete_vocabulary(&self,vocabulary_id: &str) ->Result<DeleteVocabularyResponse> {
+        self.delete_vocabulary_with_options(vocabulary_id, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项删除热词表，参见 [`Customization::delete_vocabulary`]
+    pub async fn delete_vocabulary_with_options(&self,vocabulary_id: &str, options: &RequestOptions) ->Result<DeleteVocabularyResponse> {
         #[derive(Debug, Clone, Serialize, Deserialize)]
         struct DeleteVocabularyParam {
             model: String,
@@ -231,7 +284,10 @@ impl Customization {
                 vocabulary_id: vocabulary_id.to_string(),
             },
         };
-        let resp: DeleteVocabularyResponse = self.client.post(CUSTOMIZATION_PATH, param).await?;
+        let resp: DeleteVocabularyResponse = self
+            .client
+            .post_with_options(CUSTOMIZATION_PATH, param, self.client.config().headers(), options)
+            .await?;
         Ok(resp)
     }
 
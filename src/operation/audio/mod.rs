@@ -5,6 +5,7 @@ use crate::{
 };
 #[cfg(feature = "websocket")]
 use crate::operation::audio::asr::customization::Customization;
+use crate::operation::request::RequestOptions;
 use crate::{error::Result, operation::audio::tts::output::TextToSpeechOutput};
 pub use tts::param::{
     Input as TextToSpeechInput, InputBuilder as TextToSpeechInputBuilder, TextToSpeechParam,
@@ -34,23 +35,47 @@ impl<'a> Audio<'a> {
     /// # 参数
     /// * `request` - TTS 转换参数配置，包含文本内容、语音模型等设置
     pub async fn tts(&self, request: TextToSpeechParam) -> Result<TextToSpeechOutput> {
+        self.tts_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项执行文本转语音，参见 [`Audio::tts`]
+    pub async fn tts_with_options(
+        &self,
+        request: TextToSpeechParam,
+        options: &RequestOptions,
+    ) -> Result<TextToSpeechOutput> {
         // 检查请求是否明确设置为非流式，如果是，则返回错误。
         if request.stream == Some(true) {
             return Err(DashScopeError::InvalidArgument(
                 "When stream is true, use Audio::call_stream".into(),
             ));
         }
-        self.client.post(AUDIO_PATH, request).await
+        self.client
+            .post_with_options(AUDIO_PATH, request, self.client.config().headers(), options)
+            .await
     }
 
     pub async fn tts_stream(&self, request: TextToSpeechParam) -> Result<TextToSpeechOutputStream> {
+        self.tts_stream_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项执行流式文本转语音，参见 [`Audio::tts_stream`]
+    pub async fn tts_stream_with_options(
+        &self,
+        request: TextToSpeechParam,
+        options: &RequestOptions,
+    ) -> Result<TextToSpeechOutputStream> {
         // 检查请求是否明确设置为非流式，如果是，则返回错误。
         if request.stream == Some(false) {
             return Err(DashScopeError::InvalidArgument(
                 "When stream is false, use Audio::call".into(),
             ));
         }
-        self.client.post_stream(AUDIO_PATH, request).await
+        self.client
+            .post_stream_with_options(AUDIO_PATH, request, self.client.config().headers(), options)
+            .await
     }
 
     #[cfg(feature = "websocket")]
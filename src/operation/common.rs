@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 #[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct Parameters {
     /// 返回数据的格式。推荐优先设置为"message"
     #[builder(setter(into, strip_option))]
@@ -34,6 +35,12 @@ pub struct Parameters {
     #[builder(default=None)]
     pub parallel_tool_calls: Option<bool>,
 
+    /// 工具选择策略，参见 [`ToolChoice`]。强制调用的函数必须在 `tools` 中声明。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    pub tool_choice: Option<ToolChoice>,
+
     // 限制思考长度
     // 该参数仅支持Qwen3 模型设定。
     #[builder(setter(into, strip_option))]
@@ -106,6 +113,357 @@ pub struct Parameters {
     #[builder(setter(into, strip_option))]
     #[builder(default=None)]
     seed: Option<i32>,
+
+    /// 采样温度，控制生成文本的多样性。取值范围：[0, 2)，越高越多样。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    temperature: Option<f32>,
+
+    /// 核采样的概率阈值，取值范围：(0, 1.0]，越高越多样。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    top_p: Option<f32>,
+
+    /// 生成过程中采样候选集的大小。取值为 0 或大于 100 时不启用 top_k 策略，仅 top_p 生效。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    top_k: Option<u32>,
+
+    /// 本次请求返回的最大 Token 数，默认值与最大值为模型的最大输出长度。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    max_tokens: Option<u32>,
+
+    /// 停止词。模型生成的文本即将包含停止词（或停止词的 Token ID）时停止生成。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    stop: Option<Stop>,
+
+    /// 生成回复的个数，取值范围：1-4。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    n: Option<u32>,
+
+    /// 是否返回输出 Token 的对数概率。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    logprobs: Option<bool>,
+
+    /// 每个生成位置返回的候选 Token 个数，取值范围：[0, 5]，仅当 `logprobs` 为 true 时生效。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    top_logprobs: Option<u32>,
+
+    /// 是否开启代码解释器，仅思考模式下的部分模型支持。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    enable_code_interpreter: Option<bool>,
+}
+
+/// 停止词，可以是字符串或 Token ID，但不能混用
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Stop {
+    Text(String),
+    Texts(Vec<String>),
+    TokenIds(Vec<Vec<u32>>),
+}
+
+impl From<&str> for Stop {
+    fn from(value: &str) -> Self {
+        Self::Text(value.into())
+    }
+}
+
+impl From<String> for Stop {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<Vec<String>> for Stop {
+    fn from(value: Vec<String>) -> Self {
+        Self::Texts(value)
+    }
+}
+
+impl From<Vec<&str>> for Stop {
+    fn from(value: Vec<&str>) -> Self {
+        Self::Texts(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Vec<Vec<u32>>> for Stop {
+    fn from(value: Vec<Vec<u32>>) -> Self {
+        Self::TokenIds(value)
+    }
+}
+
+/// 工具选择策略
+///
+/// 序列化为 `"auto"`、`"none"`、`"required"` 或
+/// `{"type": "function", "function": {"name": "..."}}`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ToolChoiceRepr", into = "ToolChoiceRepr")]
+pub enum ToolChoice {
+    /// 由模型决定是否调用工具
+    Auto,
+    /// 不调用工具
+    None,
+    /// 必须调用至少一个工具
+    Required,
+    /// 必须调用指定名称的函数
+    Function(String),
+}
+
+impl ToolChoice {
+    pub fn function(name: impl Into<String>) -> Self {
+        Self::Function(name.into())
+    }
+
+    /// 是否强制模型调用工具
+    pub fn is_forced(&self) -> bool {
+        matches!(self, Self::Required | Self::Function(_))
+    }
+
+    /// 校验与工具列表是否一致
+    fn check(&self, tools: &[FunctionCall]) -> Result<(), String> {
+        match self {
+            Self::Required if tools.is_empty() => {
+                Err("tool_choice `required` requires at least one tool".into())
+            }
+            Self::Function(name) => {
+                let declared = tools
+                    .iter()
+                    .filter_map(|tool| tool.function.as_ref())
+                    .any(|function| function.name() == name);
+                if declared {
+                    Ok(())
+                } else {
+                    Err(format!("tool_choice function `{name}` is not declared in tools"))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ToolChoiceRepr {
+    Mode(String),
+    Function {
+        #[serde(rename = "type")]
+        type_: String,
+        function: ToolChoiceFunction,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct ToolChoiceFunction {
+    name: String,
+}
+
+impl TryFrom<ToolChoiceRepr> for ToolChoice {
+    type Error = String;
+
+    fn try_from(value: ToolChoiceRepr) -> Result<Self, Self::Error> {
+        match value {
+            ToolChoiceRepr::Mode(mode) => match mode.as_str() {
+                "auto" => Ok(Self::Auto),
+                "none" => Ok(Self::None),
+                "required" => Ok(Self::Required),
+                _ => Err(format!("unknown tool_choice `{mode}`")),
+            },
+            ToolChoiceRepr::Function { type_, function } if type_ == "function" => {
+                Ok(Self::Function(function.name))
+            }
+            ToolChoiceRepr::Function { type_, .. } => {
+                Err(format!("unknown tool_choice type `{type_}`"))
+            }
+        }
+    }
+}
+
+impl From<ToolChoice> for ToolChoiceRepr {
+    fn from(value: ToolChoice) -> Self {
+        match value {
+            ToolChoice::Auto => Self::Mode("auto".into()),
+            ToolChoice::None => Self::Mode("none".into()),
+            ToolChoice::Required => Self::Mode("required".into()),
+            ToolChoice::Function(name) => Self::Function {
+                type_: "function".into(),
+                function: ToolChoiceFunction { name },
+            },
+        }
+    }
+}
+
+/// 需要校验取值范围的采样参数
+#[derive(Default)]
+struct Sampling {
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    n: Option<u32>,
+    logprobs: Option<bool>,
+    top_logprobs: Option<u32>,
+    presence_penalty: Option<f64>,
+    repetition_penalty: Option<f64>,
+    seed: Option<i32>,
+}
+
+impl Sampling {
+    fn check(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature.filter(|t| !(0.0..2.0).contains(t)) {
+            return Err(format!("temperature must be in [0, 2), got {t}"));
+        }
+        if let Some(p) = self.top_p.filter(|p| !(*p > 0.0 && *p <= 1.0)) {
+            return Err(format!("top_p must be in (0, 1], got {p}"));
+        }
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be greater than 0".into());
+        }
+        if let Some(n) = self.n.filter(|n| !(1..=4).contains(n)) {
+            return Err(format!("n must be in [1, 4], got {n}"));
+        }
+        if let Some(k) = self.top_logprobs {
+            if k > 5 {
+                return Err(format!("top_logprobs must be in [0, 5], got {k}"));
+            }
+            if self.logprobs != Some(true) {
+                return Err("top_logprobs requires logprobs = true".into());
+            }
+        }
+        if let Some(p) = self.presence_penalty.filter(|p| !(-2.0..=2.0).contains(p)) {
+            return Err(format!("presence_penalty must be in [-2, 2], got {p}"));
+        }
+        if let Some(p) = self.repetition_penalty.filter(|p| *p <= 0.0) {
+            return Err(format!("repetition_penalty must be greater than 0, got {p}"));
+        }
+        if let Some(seed) = self.seed.filter(|seed| *seed < 0) {
+            return Err(format!("seed must be in [0, 2147483647], got {seed}"));
+        }
+        Ok(())
+    }
+}
+
+impl ParametersBuilder {
+    fn validate(&self) -> Result<(), String> {
+        Sampling {
+            temperature: self.temperature.flatten(),
+            top_p: self.top_p.flatten(),
+            max_tokens: self.max_tokens.flatten(),
+            n: self.n.flatten(),
+            logprobs: self.logprobs.flatten(),
+            top_logprobs: self.top_logprobs.flatten(),
+            presence_penalty: self.presence_penalty.flatten(),
+            repetition_penalty: self.repetition_penalty.flatten(),
+            seed: self.seed.flatten(),
+        }
+        .check()?;
+        // 没有设置 `tools` 时可能由 `run_with_tools` 在调用时追加，留到调用前再校验
+        if let (Some(Some(tool_choice)), Some(Some(tools))) = (&self.tool_choice, &self.tools) {
+            tool_choice.check(tools)?;
+        }
+        Ok(())
+    }
+}
+
+impl Parameters {
+    /// 校验参数的取值范围，反序列化得到的参数不会经过构建器的校验
+    pub fn validate(&self) -> crate::error::Result<()> {
+        Sampling {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            n: self.n,
+            logprobs: self.logprobs,
+            top_logprobs: self.top_logprobs,
+            presence_penalty: self.presence_penalty,
+            repetition_penalty: self.repetition_penalty,
+            seed: self.seed,
+        }
+        .check()
+        .and_then(|_| match &self.tool_choice {
+            Some(tool_choice) => tool_choice.check(self.tools.as_deref().unwrap_or_default()),
+            None => Ok(()),
+        })
+        .map_err(crate::error::DashScopeError::InvalidArgument)
+    }
+
+    pub fn repetition_penalty(&self) -> Option<f64> {
+        self.repetition_penalty
+    }
+
+    pub fn presence_penalty(&self) -> Option<f64> {
+        self.presence_penalty
+    }
+
+    pub fn vl_high_resolution_images(&self) -> Option<bool> {
+        self.vl_high_resolution_images
+    }
+
+    pub fn vl_enable_image_hw_output(&self) -> Option<bool> {
+        self.vl_enable_image_hw_output
+    }
+
+    pub fn watermark(&self) -> Option<bool> {
+        self.watermark
+    }
+
+    pub fn negative_prompt(&self) -> Option<&str> {
+        self.negative_prompt.as_deref()
+    }
+
+    pub fn seed(&self) -> Option<i32> {
+        self.seed
+    }
+
+    pub fn temperature(&self) -> Option<f32> {
+        self.temperature
+    }
+
+    pub fn top_p(&self) -> Option<f32> {
+        self.top_p
+    }
+
+    pub fn top_k(&self) -> Option<u32> {
+        self.top_k
+    }
+
+    pub fn max_tokens(&self) -> Option<u32> {
+        self.max_tokens
+    }
+
+    pub fn stop(&self) -> Option<&Stop> {
+        self.stop.as_ref()
+    }
+
+    pub fn n(&self) -> Option<u32> {
+        self.n
+    }
+
+    pub fn logprobs(&self) -> Option<bool> {
+        self.logprobs
+    }
+
+    pub fn top_logprobs(&self) -> Option<u32> {
+        self.top_logprobs
+    }
+
+    pub fn enable_code_interpreter(&self) -> Option<bool> {
+        self.enable_code_interpreter
+    }
 }
 
 #[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
@@ -147,6 +505,20 @@ pub struct Function {
     parameters: Option<FunctionParameters>,
 }
 
+impl Function {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn parameters(&self) -> Option<&FunctionParameters> {
+        self.parameters.as_ref()
+    }
+}
+
 #[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
 pub struct FunctionParameters {
     #[serde(rename = "type")]
@@ -234,19 +606,49 @@ pub struct Usage {
     pub characters: Option<i32>,
 }
 
+impl Usage {
+    /// 本次调用消耗的 token 总数，未返回 `total_tokens` 时为输入与输出之和
+    pub fn total(&self) -> i32 {
+        self.total_tokens
+            .unwrap_or_else(|| self.input_tokens.unwrap_or(0) + self.output_tokens.unwrap_or(0))
+    }
+
+    /// 命中上下文缓存的输入 Token 数，未返回时为 0
+    pub fn cached_tokens(&self) -> i32 {
+        self.prompt_tokens_details
+            .as_ref()
+            .and_then(|d| d.cached_tokens)
+            .unwrap_or(0)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InputTokensDetails {
-    text_tokens: Option<i32>,
+    pub text_tokens: Option<i32>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OutputTokensDetails {
-    audio_tokens: Option<i32>,
-    text_tokens: Option<i32>,
+    pub audio_tokens: Option<i32>,
+    pub text_tokens: Option<i32>,
+    /// 思考过程的 Token 数，已包含在 `output_tokens` 中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<i32>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PromptTokensDetails {
-    /// 命中 Cache 的 Token 数。Context Cache 详情请参见上下文缓存[（Context Cache）](https://help.aliyun.com/zh/model-studio/user-guide/context-cache?spm=a2c4g.11186623.0.0.37a0453aeh9s1L)。
     pub prompt_tokens: Option<i32>,
+
+    /// 命中 Cache 的 Token 数。Context Cache 详情请参见上下文缓存[（Context Cache）](https://help.aliyun.com/zh/model-studio/user-guide/context-cache?spm=a2c4g.11186623.0.0.37a0453aeh9s1L)。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_tokens: Option<i32>,
+
+    /// 使用显式缓存时，本次请求新创建缓存的 Token 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<i32>,
+
+    /// 缓存类型，显式缓存为 `ephemeral`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_type: Option<String>,
 }
 
 
@@ -265,4 +667,92 @@ pub enum TaskStatus {
     Canceled,
     #[serde(rename = "UNKNOWN")]
     Unknown,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameters_validation() {
+        let parameters = ParametersBuilder::default()
+            .temperature(0.7)
+            .top_p(0.8)
+            .top_k(20u32)
+            .max_tokens(512u32)
+            .stop(vec!["\n\n", "END"])
+            .n(2u32)
+            .logprobs(true)
+            .top_logprobs(3u32)
+            .seed(42)
+            .build()
+            .unwrap();
+        assert_eq!(parameters.temperature(), Some(0.7));
+        assert_eq!(parameters.n(), Some(2));
+        assert_eq!(parameters.seed(), Some(42));
+        assert_eq!(
+            parameters.stop(),
+            Some(&Stop::Texts(vec!["\n\n".into(), "END".into()]))
+        );
+
+        let json = serde_json::to_value(&parameters).unwrap();
+        assert_eq!(json["stop"], serde_json::json!(["\n\n", "END"]));
+        assert!(json.get("enable_code_interpreter").is_none());
+
+        assert!(ParametersBuilder::default().temperature(2.0).build().is_err());
+        assert!(ParametersBuilder::default().top_p(0.0).build().is_err());
+        assert!(ParametersBuilder::default().n(5u32).build().is_err());
+        assert!(ParametersBuilder::default().top_logprobs(2u32).build().is_err());
+        assert!(ParametersBuilder::default().presence_penalty(3.0).build().is_err());
+
+        let parameters: Parameters =
+            serde_json::from_value(serde_json::json!({"temperature": 3.0})).unwrap();
+        assert!(parameters.validate().is_err());
+    }
+
+    #[test]
+    fn test_tool_choice() {
+        let tool = FunctionCallBuilder::default()
+            .typ("function")
+            .function(FunctionBuilder::default().name("get_weather").build().unwrap())
+            .build()
+            .unwrap();
+
+        let parameters = ParametersBuilder::default()
+            .tools(vec![tool.clone()])
+            .tool_choice(ToolChoice::function("get_weather"))
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&parameters).unwrap();
+        assert_eq!(
+            json["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+        assert_eq!(
+            serde_json::to_value(ToolChoice::Required).unwrap(),
+            serde_json::json!("required")
+        );
+        let parsed: ToolChoice = serde_json::from_value(json["tool_choice"].clone()).unwrap();
+        assert_eq!(parsed, ToolChoice::function("get_weather"));
+        assert!(serde_json::from_value::<ToolChoice>(serde_json::json!("always")).is_err());
+
+        assert!(
+            ParametersBuilder::default()
+                .tools(vec![tool])
+                .tool_choice(ToolChoice::function("search"))
+                .build()
+                .is_err()
+        );
+
+        // 未设置 tools 时留到调用前校验
+        let parameters = ParametersBuilder::default()
+            .tool_choice(ToolChoice::Required)
+            .build()
+            .unwrap();
+        assert!(parameters.validate().is_err());
+        let parameters = ParametersBuilder::default()
+            .tool_choice(ToolChoice::None)
+            .build()
+            .unwrap();
+        assert!(parameters.validate().is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::operation::request::RequestOptions;
 use crate::{error::Result, operation::validate::Validator};
 use crate::{operation::validate::check_model_parameters, Client};
 pub use output::*;
@@ -32,6 +33,16 @@ impl<'a> Embeddings<'a> {
     /// 如果操作成功，返回一个包含嵌入向量和其他相关信息的结构体
     /// 如果操作失败，返回一个错误类型，便于错误处理和调试
     pub async fn call(&self, request: param::EmbeddingsParam) -> Result<output::EmbeddingsOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项调用文本嵌入服务，参见 [`Embeddings::call`]
+    pub async fn call_with_options(
+        &self,
+        request: param::EmbeddingsParam,
+        options: &RequestOptions,
+    ) -> Result<output::EmbeddingsOutput> {
         // Validate parameters before making the request.
         let validators = check_model_parameters(&request.model);
         for valid in validators {
@@ -40,6 +51,13 @@ impl<'a> Embeddings<'a> {
 
         // 发送POST请求到指定的服务端点，并传递请求参数
         // 该行代码是异步执行的，允许在等待网络操作时继续执行其他任务，提高程序效率
-        self.client.post(EMBEDDINGS_PATH, request).await
+        self.client
+            .post_with_options(
+                EMBEDDINGS_PATH,
+                request,
+                self.client.config().headers(),
+                options,
+            )
+            .await
     }
 }
@@ -1,6 +1,6 @@
 pub mod output;
 pub mod param;
-use crate::{Client, error::DashScopeError};
+use crate::{Client, error::DashScopeError, operation::request::RequestOptions};
 
 const FILE_PATH: &str = "files";
 
@@ -44,6 +44,18 @@ impl<'a> File<'a> {
         files: Vec<&str>, 
         purpose: FilePurpose, 
         descriptions: Option<Vec<&str>>
+    ) -> Result<crate::operation::file::output::FileUploadOutput, DashScopeError> {
+        self.create_with_options(files, purpose, descriptions, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项上传文件，参见 [`File::create`]
+    pub async fn create_with_options(
+        &self,
+        files: Vec<&str>,
+        purpose: FilePurpose,
+        descriptions: Option<Vec<&str>>,
+        options: &RequestOptions,
     ) -> Result<crate::operation::file::output::FileUploadOutput, DashScopeError> {
         use reqwest::multipart;
         use std::path::Path;
@@ -101,19 +113,29 @@ impl<'a> File<'a> {
             }
 
             form_with_files
-        }).await
+        }, options).await
     }
 
     /// 查询文件信息
     pub async fn retrieve(
         &self,
         file_id: &str,
+    ) -> Result<crate::operation::file::output::FileRetrieveOutput, DashScopeError> {
+        self.retrieve_with_options(file_id, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项查询文件信息，参见 [`File::retrieve`]
+    pub async fn retrieve_with_options(
+        &self,
+        file_id: &str,
+        options: &RequestOptions,
     ) -> Result<crate::operation::file::output::FileRetrieveOutput, DashScopeError> {
         // 构建路径
         let path = format!("files/{}", file_id);
 
         // 使用客户端的get_with_params方法发送请求，参数为空对象
-        self.client.get_with_params(&path, &()).await
+        self.client.get_with_params(&path, &(), options).await
     }
 
     /// 查询文件列表
@@ -121,6 +143,17 @@ impl<'a> File<'a> {
         &self,
         page_no: Option<u64>,
         page_size: Option<u64>,
+    ) -> Result<crate::operation::file::output::FileListOutput, DashScopeError> {
+        self.list_with_options(page_no, page_size, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项查询文件列表，参见 [`File::list`]
+    pub async fn list_with_options(
+        &self,
+        page_no: Option<u64>,
+        page_size: Option<u64>,
+        options: &RequestOptions,
     ) -> Result<crate::operation::file::output::FileListOutput, DashScopeError> {
         use serde_json::json;
 
@@ -142,19 +175,29 @@ impl<'a> File<'a> {
         });
 
         // 使用客户端的get方法发送请求
-        self.client.get_with_params("files", &params).await
+        self.client.get_with_params("files", &params, options).await
     }
 
     /// 删除文件
     pub async fn delete(
         &self,
         file_id: &str,
+    ) -> Result<crate::operation::file::output::FileDeleteOutput, DashScopeError> {
+        self.delete_with_options(file_id, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项删除文件，参见 [`File::delete`]
+    pub async fn delete_with_options(
+        &self,
+        file_id: &str,
+        options: &RequestOptions,
     ) -> Result<crate::operation::file::output::FileDeleteOutput, DashScopeError> {
         // 构建路径
         let path = format!("files/{}", file_id);
 
         // 使用客户端的delete方法发送请求
-        self.client.delete(&path).await
+        self.client.delete(&path, options).await
     }
 }
 
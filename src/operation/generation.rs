@@ -1,13 +1,35 @@
+use crate::operation::{
+    common::{ParametersBuilder, ResponseFormat, ToolChoice},
+    request::RequestOptions,
+    tool::{ToolError, ToolRegistry, ToolRunOutput},
+};
 use crate::{client::Client, error::DashScopeError, operation::validate::Validator};
 use crate::{error::Result, operation::validate::check_model_parameters};
+use crate::schema::strip_code_fence;
+use serde::de::DeserializeOwned;
+pub use choices::{ChoiceDelta, ChoiceDeltaStream, split_choices};
+pub use context::{
+    ContextManager, DropOldest, KeepLastTurns, Summarize, TruncateFuture, Truncated,
+    TruncationInput, TruncationReport, TruncationStrategy,
+};
+pub use event::{GenerationEvent, GenerationEventStream, into_events};
+pub use json::{DEFAULT_MAX_REPAIRS, JsonOptions, JsonOptionsBuilder, JsonOutput};
+pub use logprobs::{Logprobs, TokenLogprob, TopLogprob};
 pub use output::*;
+pub use session::{ChatSession, ChatStream};
 pub use param::{
     AssistantMessageBuilder, GenerationParam, GenerationParamBuilder, InputBuilder, MessageBuilder,
     SystemMessageBuilder, ToolMessageBuilder, UserMessageBuilder,
 };
 
+mod choices;
+mod context;
+mod event;
+mod json;
+mod logprobs;
 mod output;
-mod param;
+pub mod param;
+mod session;
 
 const GENERATION_PATH: &str = "/services/aigc/text-generation/generation";
 
@@ -31,6 +53,16 @@ impl<'a> Generation<'a> {
     /// # 返回
     /// 返回生成输出的结果，如果请求配置了stream且为true，则返回错误
     pub async fn call(&self, request: GenerationParam) -> Result<GenerationOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项（超时、额外请求头、取消令牌等）调用生成服务，参见 [`Generation::call`]
+    pub async fn call_with_options(
+        &self,
+        mut request: GenerationParam,
+        options: &RequestOptions,
+    ) -> Result<GenerationOutput> {
         // 检查请求是否启用了流式生成，如果是，则返回错误
         if request.stream == Some(true) {
             return Err(DashScopeError::InvalidArgument(
@@ -43,9 +75,24 @@ impl<'a> Generation<'a> {
         for valid in validators {
             valid.validate(&request)?;
         }
+        if let Some(parameters) = &request.parameters {
+            parameters.validate()?;
+        }
+
+        // 按模型上下文长度截断历史消息
+        if let Some(context_manager) = &self.client.context_manager {
+            context_manager.fit(self.client, &mut request).await?;
+        }
 
         // 发送POST请求到生成服务，并等待结果
-        self.client.post(GENERATION_PATH, request).await
+        self.client
+            .post_with_options(
+                GENERATION_PATH,
+                request,
+                self.client.config().headers(),
+                options,
+            )
+            .await
     }
 
     /// 异步调用生成流函数
@@ -67,9 +114,16 @@ impl<'a> Generation<'a> {
     ///
     /// # 注意
     /// 该函数自动将 `request` 的 `stream` 属性设置为 `Some(true)`，确保总是以流式处理方式执行生成任务。
-    pub async fn call_stream(
+    pub async fn call_stream(&self, request: GenerationParam) -> Result<GenerationOutputStream> {
+        self.call_stream_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项调用流式生成服务，参见 [`Generation::call_stream`]
+    pub async fn call_stream_with_options(
         &self,
         mut request: GenerationParam,
+        options: &RequestOptions,
     ) -> Result<GenerationOutputStream> {
         // 检查 `request` 中的 `stream` 属性，如果明确为 `false`，则返回错误
         if request.stream == Some(false) {
@@ -86,11 +140,245 @@ impl<'a> Generation<'a> {
         for valid in validators {
             valid.validate(&request)?;
         }
+        if let Some(parameters) = &request.parameters {
+            parameters.validate()?;
+        }
+
+        if let Some(context_manager) = &self.client.context_manager {
+            context_manager.fit(self.client, &mut request).await?;
+        }
 
         let mut headers = self.client.config().headers();
         headers.insert("X-DashScope-SSE", "enable".parse().unwrap());
 
         // 通过客户端发起 POST 请求，使用修改后的 `request` 对象，并等待异步响应
-        self.client.post_stream_with_headers(GENERATION_PATH, request, headers).await
+        self.client
+            .post_stream_with_options(GENERATION_PATH, request, headers, options)
+            .await
+    }
+
+    /// 以增量事件的形式调用流式生成服务
+    ///
+    /// 与 [`Generation::call_stream`] 不同，返回的流中每一项都是一个 [`GenerationEvent`]，
+    /// 可以直接区分思考过程、回复内容、工具调用进度与结束原因。为保证事件是增量内容，
+    /// 该方法会强制开启 `incremental_output`。
+    pub async fn call_stream_events(&self, request: GenerationParam) -> Result<GenerationEventStream> {
+        self.call_stream_events_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项以增量事件的形式调用流式生成服务，参见 [`Generation::call_stream_events`]
+    pub async fn call_stream_events_with_options(
+        &self,
+        mut request: GenerationParam,
+        options: &RequestOptions,
+    ) -> Result<GenerationEventStream> {
+        let parameters = match request.parameters.as_mut() {
+            Some(parameters) => parameters,
+            None => request
+                .parameters
+                .insert(ParametersBuilder::default().build()?),
+        };
+        #[allow(deprecated)]
+        {
+            parameters.incremental_output = Some(true);
+        }
+
+        let stream = self.call_stream_with_options(request, options).await?;
+        Ok(into_events(stream))
+    }
+
+    /// 自动执行工具调用直到模型给出最终回复
+    ///
+    /// 会把 `tools` 中的工具定义追加到请求参数中，然后循环：发送请求，若模型返回工具调用，
+    /// 则依次（`parallel_tool_calls` 为 `true` 时并发）执行，把结果作为工具消息追加到对话中，
+    /// 再次请求。超过 [`ToolRegistry::max_iterations`] 轮仍未得到最终回复时返回
+    /// [`ToolError::MaxIterations`]，其中带有截至此时的对话记录，可以据此继续对话。
+    /// `tool_choice` 强制调用工具时只作用于第一轮请求，之后改为 [`ToolChoice::Auto`]。
+    pub async fn run_with_tools(
+        &self,
+        request: GenerationParam,
+        tools: &ToolRegistry,
+    ) -> Result<ToolRunOutput> {
+        self.run_with_tools_with_options(request, tools, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项自动执行工具调用，参见 [`Generation::run_with_tools`]
+    pub async fn run_with_tools_with_options(
+        &self,
+        mut request: GenerationParam,
+        tools: &ToolRegistry,
+        options: &RequestOptions,
+    ) -> Result<ToolRunOutput> {
+        let parameters = match request.parameters.as_mut() {
+            Some(parameters) => parameters,
+            None => request
+                .parameters
+                .insert(ParametersBuilder::default().build()?),
+        };
+        let definitions = parameters.tools.get_or_insert_with(Vec::new);
+        for definition in tools.definitions() {
+            let name = definition.function.as_ref().map(|f| f.name());
+            if !definitions
+                .iter()
+                .any(|d| d.function.as_ref().map(|f| f.name()) == name)
+            {
+                definitions.push(definition);
+            }
+        }
+        if parameters.result_format.is_none() {
+            parameters.result_format = Some("message".into());
+        }
+        let parallel = parameters.parallel_tool_calls == Some(true);
+
+        for iteration in 1..=tools.get_max_iterations() {
+            let output = self.call_with_options(request.clone(), options).await?;
+
+            let Some(message) = output
+                .output
+                .choices
+                .as_ref()
+                .and_then(|choices| choices.first())
+                .map(|choice| choice.message.clone())
+            else {
+                return Ok(ToolRunOutput {
+                    output,
+                    messages: request.input.messages,
+                    iterations: iteration,
+                });
+            };
+            let calls = message.tool_calls.clone().unwrap_or_default();
+            if calls.is_empty() {
+                request.input.messages.push(
+                    MessageBuilder::new("assistant", message.content).build()?,
+                );
+                return Ok(ToolRunOutput {
+                    output,
+                    messages: request.input.messages,
+                    iterations: iteration,
+                });
+            }
+
+            request.input.messages.push(
+                MessageBuilder::new("assistant", message.content)
+                    .tool_calls(calls.iter().map(param::ToolCall::from).collect())
+                    .build()?,
+            );
+
+            let results = if parallel {
+                futures_util::future::join_all(calls.iter().map(|call| tools.execute(call))).await
+            } else {
+                let mut results = Vec::with_capacity(calls.len());
+                for call in &calls {
+                    results.push(tools.execute(call).await);
+                }
+                results
+            };
+
+            for (call, result) in calls.iter().zip(results) {
+                request.input.messages.push(
+                    MessageBuilder::new("tool", result?)
+                        .tool_call_id(call.id.clone())
+                        .build()?,
+                );
+            }
+
+            // 强制调用工具只作用于第一轮，之后由模型决定是否继续调用，否则会一直调用到最大轮数
+            if let Some(parameters) = request.parameters.as_mut() {
+                if parameters
+                    .tool_choice
+                    .as_ref()
+                    .is_some_and(ToolChoice::is_forced)
+                {
+                    parameters.tool_choice = Some(ToolChoice::Auto);
+                }
+            }
+        }
+
+        Err(ToolError::MaxIterations {
+            iterations: tools.get_max_iterations(),
+            messages: request.input.messages,
+        }
+        .into())
+    }
+
+    /// 调用生成服务并把回复解析为 `T`
+    ///
+    /// 会开启 `json_object` 输出格式；回复无法解析时把错误反馈给模型重新请求，
+    /// 最多 [`DEFAULT_MAX_REPAIRS`] 次。需要把 `T` 的 JSON Schema 写入系统提示词时，
+    /// 使用 [`Generation::call_json_with_options`] 并传入 [`JsonOptions::for_type`]。
+    pub async fn call_json<T>(&self, request: GenerationParam) -> Result<JsonOutput<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.call_json_with_options(request, &JsonOptions::default())
+            .await
+    }
+
+    /// 使用自定义选项调用生成服务并把回复解析为 `T`，参见 [`Generation::call_json`]
+    pub async fn call_json_with_options<T>(
+        &self,
+        mut request: GenerationParam,
+        options: &JsonOptions,
+    ) -> Result<JsonOutput<T>>
+    where
+        T: DeserializeOwned,
+    {
+        request.stream = Some(false);
+        let parameters = match request.parameters.as_mut() {
+            Some(parameters) => parameters,
+            None => request
+                .parameters
+                .insert(ParametersBuilder::default().build()?),
+        };
+        parameters.result_format = Some("message".into());
+        parameters.response_format = Some(ResponseFormat {
+            type_: "json_object".into(),
+        });
+        json::inject_instruction(
+            &mut request.input.messages,
+            &json::json_instruction(options.schema()),
+        );
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let output = self
+                .call_with_options(request.clone(), options.request_options())
+                .await?;
+            let raw = output
+                .output
+                .choices
+                .as_ref()
+                .and_then(|choices| choices.first())
+                .map(|choice| choice.message.content.clone())
+                .or_else(|| output.output.text.clone())
+                .unwrap_or_default();
+
+            match serde_json::from_str::<T>(strip_code_fence(raw.trim())) {
+                Ok(value) => {
+                    return Ok(JsonOutput {
+                        value,
+                        raw,
+                        output,
+                        attempts,
+                    });
+                }
+                Err(e) if attempts <= options.max_repairs() => {
+                    tracing::warn!("structured output attempt {attempts} failed: {e}");
+                    let messages = &mut request.input.messages;
+                    messages.push(MessageBuilder::new("assistant", raw).build()?);
+                    messages.push(MessageBuilder::new("user", json::repair_prompt(&e)).build()?);
+                }
+                Err(source) => {
+                    return Err(DashScopeError::StructuredOutput {
+                        source,
+                        raw_output: raw,
+                        attempts,
+                    });
+                }
+            }
+        }
     }
 }
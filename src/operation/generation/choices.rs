@@ -0,0 +1,108 @@
+use std::pin::Pin;
+
+use async_stream::try_stream;
+use tokio_stream::{Stream, StreamExt as _};
+
+use super::{Choices, GenerationOutputStream};
+use crate::error::DashScopeError;
+
+/// 某个候选回复的增量数据块
+#[derive(Debug, Clone)]
+pub struct ChoiceDelta {
+    /// 候选回复的序号
+    pub index: u32,
+    pub choice: Choices,
+}
+
+impl ChoiceDelta {
+    /// 该候选回复是否已经结束生成
+    pub fn is_finished(&self) -> bool {
+        self.choice.is_finished()
+    }
+}
+
+pub type ChoiceDeltaStream =
+    Pin<Box<dyn Stream<Item = Result<ChoiceDelta, DashScopeError>> + Send>>;
+
+/// 将 `GenerationOutputStream` 按候选回复拆分
+///
+/// 请求参数 `n` 大于 1 时，各候选回复的数据块交错下发。拆分后的每一项只属于一个候选，
+/// 可以按 `index` 分别拼接；`usage` 等请求级别的信息不包含在内。
+///
+/// ```rust,no_run
+/// # async fn run() -> async_dashscope::error::Result<()> {
+/// use async_dashscope::{Client, operation::generation::split_choices};
+/// use tokio_stream::StreamExt as _;
+/// # let request = todo!();
+///
+/// let client = Client::new();
+/// let stream = client.generation().call_stream(request).await?;
+/// let mut choices = split_choices(stream);
+/// let mut replies = vec![String::new(); 3];
+/// while let Some(delta) = choices.next().await {
+///     let delta = delta?;
+///     replies[delta.index as usize].push_str(&delta.choice.message.content);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn split_choices(mut stream: GenerationOutputStream) -> ChoiceDeltaStream {
+    Box::pin(try_stream! {
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            for (position, choice) in chunk.output.choices.into_iter().flatten().enumerate() {
+                yield ChoiceDelta {
+                    index: choice.index.unwrap_or(position as u32),
+                    choice,
+                };
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::operation::{accumulator::CollectFinal as _, generation::GenerationOutput};
+
+    fn chunks() -> GenerationOutputStream {
+        let chunks = [
+            json!({"output": {"choices": [{"index": 1, "finish_reason": "null",
+                "message": {"role": "assistant", "content": "乙"}}]}}),
+            json!({"output": {"choices": [{"index": 0, "finish_reason": "null",
+                "message": {"role": "assistant", "content": "甲"}}]}}),
+            json!({"output": {"choices": [{"index": 1, "finish_reason": "stop",
+                "message": {"role": "assistant", "content": "二"}}]}}),
+            json!({"output": {"choices": [{"index": 0, "finish_reason": "length",
+                "message": {"role": "assistant", "content": "一"}}]},
+                "usage": {"input_tokens": 3, "output_tokens": 4, "total_tokens": 7}}),
+        ]
+        .map(|v| Ok(serde_json::from_value::<GenerationOutput>(v).unwrap()));
+        Box::pin(tokio_stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn test_split_choices() {
+        let deltas: Vec<_> = split_choices(chunks())
+            .collect::<Result<Vec<_>, _>>()
+            .await
+            .unwrap();
+        let indices: Vec<_> = deltas.iter().map(|d| d.index).collect();
+        assert_eq!(indices, [1, 0, 1, 0]);
+        assert!(deltas[2].is_finished());
+        assert!(!deltas[1].is_finished());
+
+        let output = chunks().collect_final().await.unwrap();
+        let choices = output.choices();
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[0].message.content, "甲一");
+        assert_eq!(choices[0].finish_reason.as_deref(), Some("length"));
+        assert_eq!(output.choice(1).unwrap().message.content, "乙二");
+        assert_eq!(
+            output.choice(1).unwrap().finish_reason.as_deref(),
+            Some("stop")
+        );
+    }
+}
@@ -0,0 +1,544 @@
+use std::{fmt::Debug, future::Future, pin::Pin, sync::Arc};
+
+use super::param::{
+    ContentPart, GenerationParam, Message, MessageContent, SystemMessage, UserMessage,
+};
+use crate::{
+    client::Client,
+    error::{DashScopeError, Result},
+    operation::model::ModelCapability,
+    tokenizer::Tokenizer,
+};
+
+/// 摘要在系统提示词中的起始标记，再次摘要时会替换标记之后的内容
+const SUMMARY_MARKER: &str = "[对话摘要]\n";
+
+const DEFAULT_SUMMARY_PROMPT: &str = "请把下面的对话压缩为一段简洁的摘要，保留关键事实、用户的偏好与尚未完成的任务，只输出摘要内容。";
+
+pub type TruncateFuture<'a> = Pin<Box<dyn Future<Output = Result<Truncated>> + Send + 'a>>;
+type TruncateCallback = Arc<dyn Fn(&TruncationReport) + Send + Sync>;
+
+/// 截断策略的输入
+#[derive(Debug, Clone, Copy)]
+pub struct TruncationInput<'a> {
+    pub client: &'a Client,
+    pub model: &'a str,
+    /// 消息允许占用的最大 Token 数
+    pub limit: usize,
+    /// 用于计数的 Token 计数器
+    pub tokenizer: &'a Tokenizer,
+}
+
+/// 截断策略的结果
+#[derive(Debug, Clone, Default)]
+pub struct Truncated {
+    /// 截断后发送给模型的消息
+    pub messages: Vec<Message>,
+    /// 被移除（或被摘要替代）的消息，按原顺序排列
+    pub dropped: Vec<Message>,
+    /// 替代旧消息的摘要
+    pub summary: Option<String>,
+}
+
+/// 历史消息截断策略
+///
+/// 只有当消息超过 [`TruncationInput::limit`] 时才会被调用。开头的系统消息与最后一轮对话
+/// （最后一条用户消息及其之后的消息）应当保留。
+pub trait TruncationStrategy: Send + Sync + Debug {
+    fn truncate<'a>(
+        &'a self,
+        input: TruncationInput<'a>,
+        messages: Vec<Message>,
+    ) -> TruncateFuture<'a>;
+}
+
+/// 从最早的一轮对话开始丢弃，直到消息不超过限制
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropOldest;
+
+impl TruncationStrategy for DropOldest {
+    fn truncate<'a>(
+        &'a self,
+        input: TruncationInput<'a>,
+        messages: Vec<Message>,
+    ) -> TruncateFuture<'a> {
+        Box::pin(async move { Ok(drop_oldest(input, messages, Vec::new())) })
+    }
+}
+
+/// 只保留系统消息与最近 N 轮对话，仍超过限制时继续丢弃最早的对话
+#[derive(Debug, Clone, Copy)]
+pub struct KeepLastTurns(pub usize);
+
+impl TruncationStrategy for KeepLastTurns {
+    fn truncate<'a>(
+        &'a self,
+        input: TruncationInput<'a>,
+        messages: Vec<Message>,
+    ) -> TruncateFuture<'a> {
+        Box::pin(async move {
+            let (system, turns) = split_turns(messages);
+            let keep = self.0.max(1).min(turns.len());
+            let dropped = turns[..turns.len() - keep].concat();
+            let messages = system
+                .into_iter()
+                .chain(turns[turns.len() - keep..].concat())
+                .collect();
+            Ok(drop_oldest(input, messages, dropped))
+        })
+    }
+}
+
+/// 用（通常更便宜的）模型把较早的对话压缩为摘要，并写入系统提示词
+///
+/// 保留最近 `keep_last_turns` 轮对话，其余对话连同之前的摘要一起交给 `model` 生成新的摘要。
+/// 摘要后仍超过限制时继续丢弃最早的对话。
+#[derive(Debug, Clone)]
+pub struct Summarize {
+    model: String,
+    keep_last_turns: usize,
+    prompt: String,
+}
+
+impl Summarize {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            keep_last_turns: 2,
+            prompt: DEFAULT_SUMMARY_PROMPT.into(),
+        }
+    }
+
+    /// 保留最近几轮对话不参与摘要，默认为 2
+    pub fn keep_last_turns(mut self, turns: usize) -> Self {
+        self.keep_last_turns = turns.max(1);
+        self
+    }
+
+    /// 自定义生成摘要时使用的提示词
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    async fn summarize(
+        &self,
+        client: &Client,
+        previous: Option<&str>,
+        turns: &[Message],
+    ) -> Result<String> {
+        let mut transcript = String::new();
+        if let Some(previous) = previous {
+            transcript.push_str("之前的摘要：\n");
+            transcript.push_str(previous);
+            transcript.push_str("\n\n");
+        }
+        for message in turns {
+            if let Some(role) = message.role() {
+                transcript.push_str(&format!("{role}: {}\n", message.content()));
+            }
+        }
+
+        // 摘要请求不再经过上下文管理，避免递归
+        let mut client = client.clone();
+        client.context_manager = None;
+        let request = GenerationParam {
+            model: self.model.clone(),
+            input: super::param::Input {
+                messages: vec![
+                    Message::System(SystemMessage {
+                        role: "system".into(),
+                        content: self.prompt.clone().into(),
+                    }),
+                    Message::User(UserMessage {
+                        role: "user".into(),
+                        content: transcript.into(),
+                    }),
+                ],
+            },
+            parameters: None,
+            stream: Some(false),
+            stream_options: None,
+        };
+        let output = client.generation().call(request).await?;
+        let summary = output
+            .output
+            .choices
+            .as_ref()
+            .and_then(|choices| choices.first())
+            .map(|choice| choice.message.content.clone())
+            .or(output.output.text)
+            .unwrap_or_default();
+        Ok(summary.trim().to_string())
+    }
+}
+
+impl TruncationStrategy for Summarize {
+    fn truncate<'a>(
+        &'a self,
+        input: TruncationInput<'a>,
+        messages: Vec<Message>,
+    ) -> TruncateFuture<'a> {
+        Box::pin(async move {
+            let (mut system, turns) = split_turns(messages);
+            if turns.len() <= self.keep_last_turns {
+                let messages = system.into_iter().chain(turns.concat()).collect();
+                return Ok(drop_oldest(input, messages, Vec::new()));
+            }
+
+            let split = turns.len() - self.keep_last_turns;
+            let older = turns[..split].concat();
+
+            let previous = match system.first() {
+                Some(Message::System(s)) => split_summary(&s.content.text()).1.map(str::to_string),
+                _ => None,
+            };
+            let summary = self
+                .summarize(input.client, previous.as_deref(), &older)
+                .await?;
+
+            match system.first_mut() {
+                Some(Message::System(s)) => match &mut s.content {
+                    MessageContent::Text(content) => {
+                        let (prompt, _) = split_summary(content);
+                        *content = if prompt.is_empty() {
+                            format!("{SUMMARY_MARKER}{summary}")
+                        } else {
+                            format!("{prompt}\n\n{SUMMARY_MARKER}{summary}")
+                        };
+                    }
+                    // 摘要作为单独的片段放在最后，保留原有片段上的缓存标记
+                    MessageContent::Parts(parts) => {
+                        parts.retain(|part| !part.text.contains(SUMMARY_MARKER));
+                        parts.push(ContentPart::text(format!("\n\n{SUMMARY_MARKER}{summary}")));
+                    }
+                },
+                _ => system.insert(
+                    0,
+                    Message::System(SystemMessage {
+                        role: "system".into(),
+                        content: format!("{SUMMARY_MARKER}{summary}").into(),
+                    }),
+                ),
+            }
+
+            let messages = system.into_iter().chain(turns[split..].concat()).collect();
+            let mut truncated = drop_oldest(input, messages, older);
+            truncated.summary = Some(summary);
+            Ok(truncated)
+        })
+    }
+}
+
+/// 拆分系统提示词中原有的提示词与之前写入的摘要
+fn split_summary(content: &str) -> (&str, Option<&str>) {
+    match content.split_once(SUMMARY_MARKER) {
+        Some((prompt, summary)) => (prompt.trim_end(), Some(summary)),
+        None => (content, None),
+    }
+}
+
+/// 拆分为开头的系统消息与按用户消息划分的若干轮对话
+fn split_turns(messages: Vec<Message>) -> (Vec<Message>, Vec<Vec<Message>>) {
+    let mut system = Vec::new();
+    let mut turns: Vec<Vec<Message>> = Vec::new();
+    for message in messages {
+        match message {
+            Message::None => {}
+            Message::System(_) if turns.is_empty() => system.push(message),
+            Message::User(_) => turns.push(vec![message]),
+            _ => match turns.last_mut() {
+                Some(turn) => turn.push(message),
+                None => turns.push(vec![message]),
+            },
+        }
+    }
+    (system, turns)
+}
+
+/// 丢弃最早的对话直到不超过 `limit`，最后一轮对话始终保留
+fn drop_oldest(
+    input: TruncationInput<'_>,
+    messages: Vec<Message>,
+    mut dropped: Vec<Message>,
+) -> Truncated {
+    let tokenizer = input.tokenizer;
+    let (system, mut turns) = split_turns(messages);
+    let mut tokens = tokenizer.count_messages(&system)
+        + turns
+            .iter()
+            .map(|t| tokenizer.count_messages(t))
+            .sum::<usize>();
+    let mut drop = 0;
+    while tokens > input.limit && drop + 1 < turns.len() {
+        tokens -= tokenizer.count_messages(&turns[drop]);
+        drop += 1;
+    }
+    dropped.extend(turns.drain(..drop).flatten());
+    Truncated {
+        messages: system
+            .into_iter()
+            .chain(turns.into_iter().flatten())
+            .collect(),
+        dropped,
+        summary: None,
+    }
+}
+
+/// 一次截断的结果
+#[derive(Debug, Clone, Default)]
+pub struct TruncationReport {
+    pub model: String,
+    /// 消息允许占用的最大 Token 数，未知模型为 `None`
+    pub limit: Option<usize>,
+    /// 截断前消息的估算 Token 数
+    pub tokens_before: usize,
+    /// 截断后消息的估算 Token 数
+    pub tokens_after: usize,
+    /// 被移除（或被摘要替代）的消息
+    pub dropped: Vec<Message>,
+    /// 替代旧消息的摘要
+    pub summary: Option<String>,
+}
+
+impl TruncationReport {
+    /// 是否移除了消息
+    pub fn is_truncated(&self) -> bool {
+        !self.dropped.is_empty()
+    }
+}
+
+/// 上下文管理器
+///
+/// 根据模型能力表（[`ModelCapability`]）计算输入上限，消息超出时调用截断策略。
+/// 通过 [`Client::with_context_manager`] 设置后，[`Generation`](super::Generation) 的请求
+/// 会在发送前自动截断；也可以直接调用 [`ContextManager::fit`]。
+///
+/// ```rust
+/// use async_dashscope::{Client, operation::generation::{ContextManager, KeepLastTurns}};
+///
+/// let client = Client::new().with_context_manager(
+///     ContextManager::new(KeepLastTurns(10))
+///         .reserved_output(2048)
+///         .on_truncate(|report| println!("dropped {} messages", report.dropped.len())),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ContextManager {
+    strategy: Arc<dyn TruncationStrategy>,
+    reserved_output: usize,
+    capability: Option<ModelCapability>,
+    tokenizer: Tokenizer,
+    on_truncate: Option<TruncateCallback>,
+}
+
+impl Debug for ContextManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextManager")
+            .field("strategy", &self.strategy)
+            .field("reserved_output", &self.reserved_output)
+            .field("capability", &self.capability)
+            .field("tokenizer", &self.tokenizer)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ContextManager {
+    fn default() -> Self {
+        Self::new(DropOldest)
+    }
+}
+
+impl ContextManager {
+    pub fn new<S>(strategy: S) -> Self
+    where
+        S: TruncationStrategy + 'static,
+    {
+        Self {
+            strategy: Arc::new(strategy),
+            reserved_output: 0,
+            capability: None,
+            tokenizer: Tokenizer::default(),
+            on_truncate: None,
+        }
+    }
+
+    /// 为模型输出预留的 Token 数，会从上下文长度中扣除；请求的 `max_tokens` 更大时以其为准
+    pub fn reserved_output(mut self, tokens: usize) -> Self {
+        self.reserved_output = tokens;
+        self
+    }
+
+    /// 使用指定的模型能力，而不是查询内置的能力表
+    pub fn capability(mut self, capability: ModelCapability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
+    /// 使用指定的 Token 计数器，默认为启发式估算，参见 [`Tokenizer`]
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// 发生截断时的回调
+    pub fn on_truncate<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&TruncationReport) + Send + Sync + 'static,
+    {
+        self.on_truncate = Some(Arc::new(callback));
+        self
+    }
+
+    /// 截断请求中的消息使其不超过模型的输入上限
+    ///
+    /// 未知模型不做处理；截断后仍超过上限时返回 [`DashScopeError::ContextLengthExceeded`]。
+    pub async fn fit(
+        &self,
+        client: &Client,
+        request: &mut GenerationParam,
+    ) -> Result<TruncationReport> {
+        let tokens_before = self.tokenizer.count_messages(&request.input.messages);
+        let mut report = TruncationReport {
+            model: request.model.clone(),
+            tokens_before,
+            tokens_after: tokens_before,
+            ..Default::default()
+        };
+
+        let Some(capability) = self
+            .capability
+            .or_else(|| ModelCapability::lookup(&request.model))
+        else {
+            return Ok(report);
+        };
+        let tools = request
+            .parameters
+            .as_ref()
+            .and_then(|p| p.tools.as_ref())
+            .and_then(|tools| serde_json::to_string(tools).ok())
+            .map_or(0, |tools| self.tokenizer.count(&tools));
+        // 请求设置了 max_tokens 时至少为其预留输出空间
+        let reserved_output = request
+            .parameters
+            .as_ref()
+            .and_then(|p| p.max_tokens())
+            .map_or(self.reserved_output, |max| {
+                self.reserved_output.max(max as usize)
+            });
+        let limit = capability
+            .input_budget(reserved_output)
+            .saturating_sub(tools);
+        report.limit = Some(limit);
+        if tokens_before <= limit {
+            return Ok(report);
+        }
+
+        let messages = std::mem::take(&mut request.input.messages);
+        let input = TruncationInput {
+            client,
+            model: &request.model,
+            limit,
+            tokenizer: &self.tokenizer,
+        };
+        let truncated = self.strategy.truncate(input, messages).await?;
+        request.input.messages = truncated.messages;
+
+        report.tokens_after = self.tokenizer.count_messages(&request.input.messages);
+        report.dropped = truncated.dropped;
+        report.summary = truncated.summary;
+
+        tracing::info!(
+            "truncated context for {}: {} -> {} tokens, dropped {} message(s)",
+            report.model,
+            report.tokens_before,
+            report.tokens_after,
+            report.dropped.len()
+        );
+        if let Some(callback) = &self.on_truncate {
+            callback(&report);
+        }
+
+        if report.tokens_after > limit {
+            return Err(DashScopeError::ContextLengthExceeded {
+                model: report.model,
+                tokens: report.tokens_after,
+                limit,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::generation::MessageBuilder;
+
+    fn conversation(turns: usize) -> Vec<Message> {
+        let mut messages = vec![
+            MessageBuilder::new("system", "你是一个助手")
+                .build()
+                .unwrap(),
+        ];
+        for i in 0..turns {
+            messages.push(
+                MessageBuilder::new("user", format!("问题 {i}"))
+                    .build()
+                    .unwrap(),
+            );
+            messages.push(
+                MessageBuilder::new("assistant", "回答".repeat(50))
+                    .build()
+                    .unwrap(),
+            );
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_fit() {
+        let client = Client::new();
+        let manager =
+            ContextManager::new(DropOldest).capability(ModelCapability::new(400, 400, 100));
+
+        let mut request = GenerationParam {
+            model: "custom".into(),
+            input: super::super::param::Input {
+                messages: conversation(2),
+            },
+            parameters: None,
+            stream: Some(false),
+            stream_options: None,
+        };
+        let report = manager.fit(&client, &mut request).await.unwrap();
+        assert!(!report.is_truncated());
+        assert_eq!(request.input.messages.len(), 5);
+
+        request.input.messages = conversation(10);
+        let report = manager.fit(&client, &mut request).await.unwrap();
+        assert!(report.is_truncated());
+        assert!(report.tokens_after <= 400);
+        assert_eq!(report.dropped.len() + request.input.messages.len(), 21);
+        assert!(matches!(request.input.messages[0], Message::System(_)));
+        assert!(matches!(request.input.messages[1], Message::User(_)));
+        assert_eq!(
+            request.input.messages[request.input.messages.len() - 2].content(),
+            "问题 9"
+        );
+
+        let manager = ContextManager::new(KeepLastTurns(1))
+            .capability(ModelCapability::new(10_000, 10_000, 100));
+        request.input.messages = conversation(3);
+        let report = manager.fit(&client, &mut request).await.unwrap();
+        assert!(!report.is_truncated());
+
+        let manager = ContextManager::new(DropOldest).capability(ModelCapability::new(50, 50, 0));
+        request.input.messages = conversation(3);
+        let err = manager.fit(&client, &mut request).await.unwrap_err();
+        assert!(matches!(
+            err,
+            DashScopeError::ContextLengthExceeded { limit: 50, .. }
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+use std::pin::Pin;
+
+use async_stream::try_stream;
+use tokio_stream::{Stream, StreamExt as _};
+
+use super::{GenerationOutput, GenerationOutputStream, SearchInfo};
+use crate::{error::DashScopeError, operation::common::Usage};
+
+/// 流式生成中的增量事件
+#[derive(Debug, Clone)]
+pub enum GenerationEvent {
+    /// 思考过程的增量内容
+    ReasoningDelta(String),
+    /// 回复内容的增量
+    ContentDelta(String),
+    /// 工具调用的增量，同一个工具调用的参数片段按 `index` 依次拼接
+    ToolCallDelta {
+        index: i32,
+        /// 仅在该工具调用的第一个数据块中出现
+        id: Option<String>,
+        /// 仅在该工具调用的第一个数据块中出现
+        name: Option<String>,
+        arguments_fragment: String,
+    },
+    /// 联网搜索的结果，只发送一次
+    SearchInfo(SearchInfo),
+    /// 生成结束
+    Finished { reason: String },
+    /// 本次调用的 token 用量，在流结束时发送
+    Usage(Usage),
+}
+
+pub type GenerationEventStream =
+    Pin<Box<dyn Stream<Item = Result<GenerationEvent, DashScopeError>> + Send>>;
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+fn is_finish_reason(reason: &Option<String>) -> bool {
+    reason
+        .as_deref()
+        .is_some_and(|r| !r.is_empty() && r != "null")
+}
+
+/// 将一个数据块拆分为增量事件，`usage` 由调用方在流结束时统一发送
+fn chunk_events(chunk: GenerationOutput, search_sent: &mut bool) -> Vec<GenerationEvent> {
+    let mut events = Vec::new();
+    let output = chunk.output;
+
+    if let Some(search_info) = output.search_info.filter(|_| !*search_sent) {
+        *search_sent = true;
+        events.push(GenerationEvent::SearchInfo(search_info));
+    }
+
+    if let Some(text) = output.text.and_then(non_empty) {
+        events.push(GenerationEvent::ContentDelta(text));
+    }
+
+    for choice in output.choices.into_iter().flatten() {
+        let message = choice.message;
+        if let Some(reasoning) = message.reasoning_content.and_then(non_empty) {
+            events.push(GenerationEvent::ReasoningDelta(reasoning));
+        }
+        if let Some(content) = non_empty(message.content) {
+            events.push(GenerationEvent::ContentDelta(content));
+        }
+        for call in message.tool_calls.into_iter().flatten() {
+            events.push(GenerationEvent::ToolCallDelta {
+                index: call.index,
+                id: non_empty(call.id),
+                name: non_empty(call.function.name),
+                arguments_fragment: call.function.arguments.unwrap_or_default(),
+            });
+        }
+        if is_finish_reason(&choice.finish_reason) {
+            events.push(GenerationEvent::Finished {
+                reason: choice.finish_reason.unwrap_or_default(),
+            });
+        }
+    }
+
+    if is_finish_reason(&output.finish_reason) {
+        events.push(GenerationEvent::Finished {
+            reason: output.finish_reason.unwrap_or_default(),
+        });
+    }
+
+    events
+}
+
+/// 将 `GenerationOutputStream` 转换为增量事件流
+///
+/// 事件不区分候选回复，请求参数 `n` 大于 1 时请使用 [`split_choices`](super::split_choices)。
+pub fn into_events(mut stream: GenerationOutputStream) -> GenerationEventStream {
+    Box::pin(try_stream! {
+        let mut search_sent = false;
+        let mut usage = None;
+        while let Some(chunk) = stream.next().await {
+            let mut chunk = chunk?;
+            if chunk.usage.is_some() {
+                usage = chunk.usage.take();
+            }
+            for event in chunk_events(chunk, &mut search_sent) {
+                yield event;
+            }
+        }
+        if let Some(usage) = usage {
+            yield GenerationEvent::Usage(usage);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_into_events() {
+        let chunks = [
+            json!({"output": {"choices": [{"finish_reason": "null",
+                "message": {"role": "assistant", "content": "", "reasoning_content": "嗯"}}]},
+                "usage": {"total_tokens": 3}}),
+            json!({"output": {"choices": [{"finish_reason": "null",
+                "message": {"role": "assistant", "content": "", "tool_calls": [
+                    {"index": 0, "id": "call_1", "type": "function",
+                     "function": {"name": "get_weather", "arguments": "{"}}]}}]}}),
+            json!({"output": {"choices": [{"finish_reason": "tool_calls",
+                "message": {"role": "assistant", "content": "好", "tool_calls": [
+                    {"index": 0, "id": "", "type": "function", "function": {"arguments": "}"}}]}}]},
+                "usage": {"total_tokens": 9}}),
+        ]
+        .map(|v| Ok(serde_json::from_value::<GenerationOutput>(v).unwrap()));
+
+        let stream: GenerationOutputStream = Box::pin(tokio_stream::iter(chunks));
+        let events: Vec<_> = into_events(stream)
+            .collect::<Result<Vec<_>, _>>()
+            .await
+            .unwrap();
+
+        assert_eq!(events.len(), 6);
+        assert!(matches!(&events[0], GenerationEvent::ReasoningDelta(s) if s == "嗯"));
+        assert!(matches!(
+            &events[1],
+            GenerationEvent::ToolCallDelta { index: 0, id: Some(id), name: Some(name), arguments_fragment }
+                if id == "call_1" && name == "get_weather" && arguments_fragment == "{"
+        ));
+        assert!(matches!(&events[2], GenerationEvent::ContentDelta(s) if s == "好"));
+        assert!(matches!(
+            &events[3],
+            GenerationEvent::ToolCallDelta {
+                id: None,
+                name: None,
+                ..
+            }
+        ));
+        assert!(
+            matches!(&events[4], GenerationEvent::Finished { reason } if reason == "tool_calls")
+        );
+        assert!(matches!(&events[5], GenerationEvent::Usage(u) if u.total() == 9));
+    }
+}
@@ -0,0 +1,116 @@
+use derive_builder::Builder;
+use serde_json::Value;
+
+use super::{GenerationOutput, param::Message};
+use crate::{operation::request::RequestOptions, schema::ToolSchema};
+
+/// 解析失败后默认重新请求的次数
+pub const DEFAULT_MAX_REPAIRS: u32 = 2;
+
+/// [`Generation::call_json_with_options`](super::Generation::call_json_with_options) 的选项
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into, strip_option))]
+pub struct JsonOptions {
+    /// 期望输出的 JSON Schema，会被写入系统提示词
+    #[builder(default)]
+    schema: Option<Value>,
+
+    /// 输出无法解析时，把错误反馈给模型并重新请求的最大次数，为 0 时不重试
+    #[builder(default = "DEFAULT_MAX_REPAIRS")]
+    max_repairs: u32,
+
+    #[builder(default)]
+    request_options: RequestOptions,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        JsonOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl JsonOptions {
+    /// 使用 `T` 的 Schema
+    pub fn for_type<T: ToolSchema>() -> Self {
+        Self {
+            schema: Some(T::schema()),
+            ..Default::default()
+        }
+    }
+
+    pub fn schema(&self) -> Option<&Value> {
+        self.schema.as_ref()
+    }
+
+    pub fn max_repairs(&self) -> u32 {
+        self.max_repairs
+    }
+
+    pub fn request_options(&self) -> &RequestOptions {
+        &self.request_options
+    }
+}
+
+/// 结构化输出的结果
+#[derive(Debug, Clone)]
+pub struct JsonOutput<T> {
+    /// 解析后的值
+    pub value: T,
+    /// 模型输出的原始文本
+    pub raw: String,
+    /// 最后一次请求的完整输出
+    pub output: GenerationOutput,
+    /// 请求次数，大于 1 表示经过了修复重试
+    pub attempts: u32,
+}
+
+/// 写入系统提示词的输出格式说明
+///
+/// 百炼的 `json_object` 模式要求提示词中包含 “JSON” 字样。
+pub(crate) fn json_instruction(schema: Option<&Value>) -> String {
+    match schema {
+        Some(schema) => format!(
+            "请只输出一个符合以下 JSON Schema 的 JSON 对象，不要输出其它任何内容：\n{}",
+            serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string())
+        ),
+        None => "请只输出一个 JSON 对象，不要输出其它任何内容。".to_string(),
+    }
+}
+
+/// 把说明追加到第一条系统消息中，没有系统消息时插入一条
+pub(crate) fn inject_instruction(messages: &mut Vec<Message>, instruction: &str) {
+    match messages.first_mut() {
+        Some(Message::System(system)) => {
+            system.content.push_str(&format!("\n\n{instruction}"));
+        }
+        _ => messages.insert(
+            0,
+            Message::System(super::param::SystemMessage {
+                role: "system".into(),
+                content: instruction.into(),
+            }),
+        ),
+    }
+}
+
+/// 把解析错误反馈给模型的提示
+pub(crate) fn repair_prompt(error: &serde_json::Error) -> String {
+    format!("上面的输出无法解析：{error}。请修正后重新输出，只输出 JSON，不要包含其它内容。")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::generation::MessageBuilder;
+
+    #[test]
+    fn test_inject_instruction() {
+        let mut messages = vec![MessageBuilder::new("user", "hi").build().unwrap()];
+        inject_instruction(&mut messages, "JSON");
+        assert!(matches!(&messages[0], Message::System(m) if m.content == "JSON"));
+
+        inject_instruction(&mut messages, "again");
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[0], Message::System(m) if m.content == "JSON\n\nagain"));
+    }
+}
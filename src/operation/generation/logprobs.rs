@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+/// 输出 Token 的对数概率，请求参数 `logprobs` 为 true 时返回
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Logprobs {
+    /// 按生成顺序排列的每个 Token 的对数概率
+    #[serde(default)]
+    pub content: Vec<TokenLogprob>,
+}
+
+/// 单个输出 Token 的对数概率
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TokenLogprob {
+    /// Token 文本
+    pub token: String,
+
+    /// Token 的 UTF-8 字节，一个字符被拆分为多个 Token 时 `token` 可能无法单独显示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+
+    /// 对数概率
+    pub logprob: f64,
+
+    /// 该位置概率最高的候选 Token，数量由 `top_logprobs` 决定
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// 候选 Token 的对数概率
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TopLogprob {
+    pub token: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+
+    pub logprob: f64,
+}
+
+impl Logprobs {
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// 整个输出序列的对数似然，即各 Token 对数概率之和
+    pub fn log_likelihood(&self) -> f64 {
+        self.content.iter().map(|t| t.logprob).sum()
+    }
+
+    /// 平均每个 Token 的对数概率，没有 Token 时返回 `None`
+    pub fn mean_logprob(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.log_likelihood() / self.len() as f64)
+    }
+
+    /// 困惑度，越低表示模型对输出越确定
+    pub fn perplexity(&self) -> Option<f64> {
+        self.mean_logprob().map(|mean| (-mean).exp())
+    }
+
+    /// 每个 Token 的概率
+    pub fn confidences(&self) -> Vec<f64> {
+        self.content.iter().map(TokenLogprob::probability).collect()
+    }
+
+    /// 概率最低的 Token
+    pub fn least_confident(&self) -> Option<&TokenLogprob> {
+        self.content
+            .iter()
+            .min_by(|a, b| a.logprob.total_cmp(&b.logprob))
+    }
+}
+
+impl TokenLogprob {
+    /// Token 的概率
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+
+    /// 与概率最高的其他候选 Token 之间的对数概率差，没有其他候选时返回 `None`
+    ///
+    /// 差值越大表示模型在该位置越确定。
+    pub fn margin(&self) -> Option<f64> {
+        self.top_logprobs
+            .iter()
+            .filter(|t| t.token != self.token)
+            .map(|t| t.logprob)
+            .max_by(f64::total_cmp)
+            .map(|best| self.logprob - best)
+    }
+
+    /// 在候选 Token 范围内归一化的概率分布，按概率从高到低排列
+    ///
+    /// 适合把第一个输出 Token 作为分类标签的场景：候选之外的概率被忽略，
+    /// 各候选的概率之和为 1。候选中不包含实际输出的 Token 时会将其加入。
+    pub fn distribution(&self) -> Vec<(String, f64)> {
+        let mut candidates: Vec<(String, f64)> = self
+            .top_logprobs
+            .iter()
+            .map(|t| (t.token.clone(), t.logprob))
+            .collect();
+        if !candidates.iter().any(|(token, _)| token == &self.token) {
+            candidates.push((self.token.clone(), self.logprob));
+        }
+
+        // 减去最大值再取指数，避免下溢
+        let max = candidates
+            .iter()
+            .map(|(_, logprob)| *logprob)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = candidates.iter().map(|(_, l)| (l - max).exp()).sum();
+        for (_, logprob) in candidates.iter_mut() {
+            *logprob = (*logprob - max).exp() / total;
+        }
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates
+    }
+}
+
+impl TopLogprob {
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logprobs() {
+        let logprobs: Logprobs = serde_json::from_value(serde_json::json!({
+            "content": [
+                {
+                    "token": "正面",
+                    "bytes": [230, 173, 163, 233, 157, 162],
+                    "logprob": -0.1,
+                    "top_logprobs": [
+                        {"token": "正面", "bytes": [230, 173, 163, 233, 157, 162], "logprob": -0.1},
+                        {"token": "负面", "bytes": [232, 180, 159, 233, 157, 162], "logprob": -2.5}
+                    ]
+                },
+                {"token": "。", "logprob": -0.3, "top_logprobs": []}
+            ]
+        }))
+        .unwrap();
+
+        assert!((logprobs.log_likelihood() + 0.4).abs() < 1e-9);
+        assert!((logprobs.mean_logprob().unwrap() + 0.2).abs() < 1e-9);
+        assert!((logprobs.perplexity().unwrap() - 0.2_f64.exp()).abs() < 1e-9);
+        assert_eq!(logprobs.least_confident().unwrap().token, "。");
+
+        let first = &logprobs.content[0];
+        assert!((first.margin().unwrap() - 2.4).abs() < 1e-9);
+        assert_eq!(logprobs.content[1].margin(), None);
+
+        let distribution = first.distribution();
+        assert_eq!(distribution[0].0, "正面");
+        assert!((distribution.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(distribution[0].1 > 0.9);
+
+        assert_eq!(Logprobs::default().perplexity(), None);
+    }
+}
@@ -4,17 +4,18 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio_stream::Stream;
 
+use super::Logprobs;
 use crate::{error::DashScopeError, operation::common::Usage};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Message {
     /// 输出消息的内容。当使用qwen-vl或qwen-audio系列模型时为array，其余情况为string。
-    #[serde(rename = "content")]
+    #[serde(rename = "content", default)]
     pub content: String,
 
     /// 输出消息的角色，固定为assistant。
-    #[serde(rename = "role")]
+    #[serde(rename = "role", default)]
     pub role: String,
 
     // 思考内容
@@ -29,13 +30,14 @@ pub struct Message {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ToolCall {
-    #[serde(rename = "id")]
+    /// 流式输出时只有第一个数据块包含 id，后续数据块为空字符串或缺失
+    #[serde(rename = "id", default)]
     pub id: String,
 
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default)]
     pub type_: String,
 
-    #[serde(rename = "index")]
+    #[serde(rename = "index", default)]
     pub index: i32,
 
     #[serde(rename = "function")]
@@ -44,12 +46,18 @@ pub struct ToolCall {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Function {
+    #[serde(default)]
     pub name: String,
     pub arguments: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Choices {
+    /// 候选回复的序号，请求参数 `n` 大于 1 时用于区分各个候选。
+    /// 流式输出时每个数据块只包含部分候选，需要按该字段归并。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+
     /// 有四种情况：
     /// - 正在生成时为null；
     /// - 因模型输出自然结束，或触发输入参数中的stop条件而结束时为stop；
@@ -61,6 +69,10 @@ pub struct Choices {
     /// 模型输出的消息对象。
     #[serde(rename = "message")]
     pub message: Message,
+
+    /// 输出 Token 的对数概率，请求参数 `logprobs` 为 true 时返回。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Logprobs>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Output {
@@ -124,5 +136,30 @@ pub struct GenerationOutput {
     pub usage: Option<Usage>,
 }
 
+impl Choices {
+    /// 是否已经结束生成，流式过程中 `finish_reason` 为 `"null"` 字符串
+    pub fn is_finished(&self) -> bool {
+        self.finish_reason
+            .as_deref()
+            .is_some_and(|reason| !reason.is_empty() && reason != "null")
+    }
+}
+
+impl GenerationOutput {
+    /// 全部候选回复，`result_format` 为 `text` 时为空
+    pub fn choices(&self) -> &[Choices] {
+        self.output.choices.as_deref().unwrap_or_default()
+    }
+
+    /// 按序号查找候选回复，没有 `index` 字段时按在数组中的位置
+    pub fn choice(&self, index: u32) -> Option<&Choices> {
+        self.choices()
+            .iter()
+            .enumerate()
+            .find(|(position, choice)| choice.index.unwrap_or(*position as u32) == index)
+            .map(|(_, choice)| choice)
+    }
+}
+
 pub type GenerationOutputStream =
     Pin<Box<dyn Stream<Item = Result<GenerationOutput, DashScopeError>> + Send>>;
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -38,7 +40,7 @@ impl InputBuilder {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 #[serde(untagged)]
 #[derive(Default)]
 pub enum Message {
@@ -50,10 +52,238 @@ pub enum Message {
     Tool(ToolMessage),
 }
 
+impl Message {
+    /// 消息角色，`Message::None` 返回 `None`
+    pub fn role(&self) -> Option<&str> {
+        match self {
+            Message::None => None,
+            Message::System(m) => Some(&m.role),
+            Message::User(m) => Some(&m.role),
+            Message::Assistant(m) => Some(&m.role),
+            Message::Tool(m) => Some(&m.role),
+        }
+    }
+
+    /// 消息文本内容，多段内容依次拼接，`Message::None` 返回空字符串
+    pub fn content(&self) -> Cow<'_, str> {
+        match self {
+            Message::None => Cow::Borrowed(""),
+            Message::System(m) => m.content.text(),
+            Message::User(m) => m.content.text(),
+            Message::Assistant(m) => Cow::Borrowed(&m.content),
+            Message::Tool(m) => Cow::Borrowed(&m.content),
+        }
+    }
+}
+
+/// 按 `role` 字段区分消息类型，避免结构相同的系统消息与用户消息被混淆
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.is_null() {
+            return Ok(Message::None);
+        }
+        let role = value
+            .get("role")
+            .and_then(|r| r.as_str())
+            .ok_or_else(|| D::Error::missing_field("role"))?;
+        let message = match role {
+            "system" => serde_json::from_value(value.clone()).map(Message::System),
+            "user" => serde_json::from_value(value.clone()).map(Message::User),
+            "assistant" => serde_json::from_value(value.clone()).map(Message::Assistant),
+            "tool" => serde_json::from_value(value.clone()).map(Message::Tool),
+            other => {
+                return Err(D::Error::unknown_variant(
+                    other,
+                    &["system", "user", "assistant", "tool"],
+                ));
+            }
+        };
+        message.map_err(D::Error::custom)
+    }
+}
+
+/// 系统消息与用户消息的内容
+///
+/// 可以是普通字符串，也可以是由多个文本片段组成的数组。片段上可以添加
+/// [`CacheControl`] 标记，开启显式上下文缓存：标记之前（含该片段）的内容会被缓存，
+/// 后续请求以相同内容开头时命中缓存，命中的 Token 数见
+/// [`PromptTokensDetails::cached_tokens`](crate::operation::common::PromptTokensDetails::cached_tokens)。
+///
+/// ```rust
+/// use async_dashscope::operation::generation::{MessageBuilder, param::ContentPart};
+///
+/// let long_prompt = "你是一名资深的法律顾问……";
+/// let system = MessageBuilder::new("system", long_prompt).cached().build().unwrap();
+///
+/// let user = MessageBuilder::new(
+///     "user",
+///     vec![ContentPart::text("合同全文……").cached(), ContentPart::text("第三条是否有效？")],
+/// )
+/// .build()
+/// .unwrap();
+/// assert_eq!(user.content(), "合同全文……第三条是否有效？");
+/// # let _ = system;
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl MessageContent {
+    /// 文本内容，多个片段依次拼接
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            Self::Text(text) => Cow::Borrowed(text),
+            Self::Parts(parts) => match parts.as_slice() {
+                [part] => Cow::Borrowed(&part.text),
+                parts => Cow::Owned(parts.iter().map(|p| p.text.as_str()).collect()),
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Text(text) => text.is_empty(),
+            Self::Parts(parts) => parts.iter().all(|p| p.text.is_empty()),
+        }
+    }
+
+    /// 是否包含缓存标记
+    pub fn is_cached(&self) -> bool {
+        match self {
+            Self::Text(_) => false,
+            Self::Parts(parts) => parts.iter().any(|p| p.cache_control.is_some()),
+        }
+    }
+
+    /// 追加文本
+    ///
+    /// 最后一个片段带有缓存标记时追加为新的片段，不改变已缓存的内容。
+    pub fn push_str(&mut self, text: &str) {
+        match self {
+            Self::Text(content) => content.push_str(text),
+            Self::Parts(parts) => match parts.last_mut() {
+                Some(last) if last.cache_control.is_none() => last.text.push_str(text),
+                _ => parts.push(ContentPart::text(text)),
+            },
+        }
+    }
+
+    /// 在最后一个片段上添加缓存标记，普通字符串会先转换为片段数组
+    pub fn cached(self) -> Self {
+        let mut parts = match self {
+            Self::Text(text) => vec![ContentPart::text(text)],
+            Self::Parts(parts) => parts,
+        };
+        if let Some(last) = parts.last_mut() {
+            last.cache_control = Some(CacheControl::Ephemeral);
+        }
+        Self::Parts(parts)
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(value: &str) -> Self {
+        Self::Text(value.into())
+    }
+}
+
+impl From<&String> for MessageContent {
+    fn from(value: &String) -> Self {
+        Self::Text(value.clone())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(value: Vec<ContentPart>) -> Self {
+        Self::Parts(value)
+    }
+}
+
+impl From<ContentPart> for MessageContent {
+    fn from(value: ContentPart) -> Self {
+        Self::Parts(vec![value])
+    }
+}
+
+impl PartialEq<str> for MessageContent {
+    fn eq(&self, other: &str) -> bool {
+        self.text() == other
+    }
+}
+
+impl PartialEq<&str> for MessageContent {
+    fn eq(&self, other: &&str) -> bool {
+        self.text() == *other
+    }
+}
+
+/// 消息内容中的一个文本片段
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContentPart {
+    #[serde(rename = "type", default = "ContentPart::text_type")]
+    pub type_: String,
+
+    #[serde(default)]
+    pub text: String,
+
+    /// 显式缓存标记
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            type_: Self::text_type(),
+            text: text.into(),
+            cache_control: None,
+        }
+    }
+
+    /// 添加缓存标记
+    pub fn cached(mut self) -> Self {
+        self.cache_control = Some(CacheControl::Ephemeral);
+        self
+    }
+
+    fn text_type() -> String {
+        "text".into()
+    }
+}
+
+/// 显式缓存标记，序列化为 `{"type": "ephemeral"}`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CacheControl {
+    /// 有效期为 5 分钟的缓存，每次命中后重新计时
+    Ephemeral,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct MessageBuilder {
     pub role: String,
-    pub content: String,
+    pub content: MessageContent,
     pub partial: Option<bool>,
     pub tool_calls: Option<Vec<ToolCall>>,
     pub tool_call_id: Option<String>,
@@ -66,7 +296,7 @@ pub enum MessageBuilderError {
 }
 
 impl MessageBuilder {
-    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+    pub fn new(role: impl Into<String>, content: impl Into<MessageContent>) -> Self {
         Self {
             role: role.into(),
             content: content.into(),
@@ -94,11 +324,17 @@ impl MessageBuilder {
         self.role("tool")
     }
 
-    pub fn content(&mut self, value: impl Into<String>) -> &mut Self {
+    pub fn content(&mut self, value: impl Into<MessageContent>) -> &mut Self {
         self.content = value.into();
         self
     }
 
+    /// 在内容的最后一个片段上添加显式缓存标记，仅对系统消息与用户消息有效
+    pub fn cached(&mut self) -> &mut Self {
+        self.content = std::mem::take(&mut self.content).cached();
+        self
+    }
+
     pub fn partial(&mut self, value: bool) -> &mut Self {
         self.partial = Some(value);
         self
@@ -126,13 +362,13 @@ impl MessageBuilder {
             })),
             "assistant" => Ok(Message::Assistant(AssistantMessage {
                 role: self.role.clone(),
-                content: self.content.clone(),
+                content: self.content.text().into_owned(),
                 partial: self.partial,
                 tool_calls: self.tool_calls.clone(),
             })),
             "tool" => Ok(Message::Tool(ToolMessage {
                 role: self.role.clone(),
-                content: self.content.clone(),
+                content: self.content.text().into_owned(),
                 tool_call_id: self.tool_call_id.clone(),
             })),
             // 不可用的角色
@@ -147,7 +383,7 @@ pub struct SystemMessage {
     #[builder(setter(into), default = "\"system\".to_string()")]
     pub role: String,
     #[builder(setter(into))]
-    pub content: String,
+    pub content: MessageContent,
 }
 
 impl From<SystemMessage> for Message {
@@ -162,7 +398,7 @@ pub struct UserMessage {
     #[builder(setter(into), default = "\"user\".to_string()")]
     pub role: String,
     #[builder(setter(into))]
-    pub content: String,
+    pub content: MessageContent,
 }
 
 impl From<UserMessage> for Message {
@@ -222,6 +458,20 @@ pub struct Function {
     pub arguments: String,
 }
 
+impl From<&super::output::ToolCall> for ToolCall {
+    fn from(value: &super::output::ToolCall) -> Self {
+        Self {
+            id: value.id.clone(),
+            type_: value.type_.clone(),
+            function: Function {
+                name: value.function.name.clone(),
+                arguments: value.function.arguments.clone().unwrap_or_default(),
+            },
+            index: value.index,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
 pub struct ToolMessage {
     #[builder(setter(into), default = "\"tool\".to_string()")]
@@ -248,3 +498,40 @@ impl RequestTrait for GenerationParam {
         self.parameters.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_content() {
+        let system = MessageBuilder::new("system", "长提示词")
+            .cached()
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&system).unwrap(),
+            serde_json::json!({
+                "role": "system",
+                "content": [{"type": "text", "text": "长提示词", "cache_control": {"type": "ephemeral"}}]
+            })
+        );
+        let parsed: Message =
+            serde_json::from_value(serde_json::to_value(&system).unwrap()).unwrap();
+        assert_eq!(parsed, system);
+
+        let user = MessageBuilder::new("user", "你好").build().unwrap();
+        assert_eq!(
+            serde_json::to_value(&user).unwrap(),
+            serde_json::json!({"role": "user", "content": "你好"})
+        );
+
+        // 追加内容不改变带缓存标记的片段
+        let mut content = MessageContent::from("前缀").cached();
+        content.push_str("问题");
+        content.push_str("？");
+        assert!(content.is_cached());
+        assert_eq!(content, "前缀问题？");
+        assert!(matches!(&content, MessageContent::Parts(parts) if parts.len() == 2));
+    }
+}
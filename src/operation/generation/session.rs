@@ -0,0 +1,415 @@
+use std::pin::Pin;
+
+use async_stream::try_stream;
+use futures_util::{Stream, StreamExt as _};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ContextManager, GenerationOutput, MessageBuilder, TruncationReport,
+    param::{self, GenerationParam, Input, Message, MessageContent},
+};
+use crate::{
+    client::Client,
+    error::{DashScopeError, Result, map_deserialization_error},
+    operation::{
+        accumulator::StreamAccumulator,
+        common::{Parameters, ParametersBuilder},
+        request::RequestOptions,
+    },
+};
+
+/// 多轮对话的流式回复
+pub type ChatStream<'a> = Pin<Box<dyn Stream<Item = Result<GenerationOutput>> + Send + 'a>>;
+
+/// 基于 [`Generation`](super::Generation) 的多轮对话会话
+///
+/// 会话持有完整的消息历史（系统提示词、用户、助手与工具消息），每轮请求时自动携带历史，
+/// 并在收到回复后把助手消息追加到历史中。会话可以序列化为 JSON，在进程重启后恢复。
+///
+/// 思考模型（如 `deepseek-r1`、`qwq-plus`）返回的 `reasoning_content` 不会写回历史，
+/// 最近一轮的思考过程可通过 [`ChatSession::last_reasoning`] 获取；部分模型会把思考过程以
+/// `<think>...</think>` 的形式放在 `content` 中，记录历史时同样会被去除。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatSession {
+    model: String,
+    messages: Vec<Message>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parameters: Option<Parameters>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_reasoning: Option<String>,
+}
+
+impl ChatSession {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            messages: Vec::new(),
+            parameters: None,
+            last_reasoning: None,
+        }
+    }
+
+    /// 设置系统提示词，已存在时替换
+    pub fn with_system(mut self, prompt: impl Into<MessageContent>) -> Self {
+        self.set_system(prompt);
+        self
+    }
+
+    /// 设置每轮请求使用的参数
+    pub fn with_parameters(mut self, parameters: Parameters) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// 设置系统提示词，已存在时替换，否则插入到历史的最前面
+    pub fn set_system(&mut self, prompt: impl Into<MessageContent>) {
+        let message = Message::System(param::SystemMessage {
+            role: "system".into(),
+            content: prompt.into(),
+        });
+        match self.messages.first_mut() {
+            Some(first @ Message::System(_)) => *first = message,
+            _ => self.messages.insert(0, message),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn set_model(&mut self, model: impl Into<String>) {
+        self.model = model.into();
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    pub fn parameters(&self) -> Option<&Parameters> {
+        self.parameters.as_ref()
+    }
+
+    pub fn parameters_mut(&mut self) -> &mut Option<Parameters> {
+        &mut self.parameters
+    }
+
+    /// 最近一轮回复的思考过程
+    pub fn last_reasoning(&self) -> Option<&str> {
+        self.last_reasoning.as_deref()
+    }
+
+    /// 追加任意消息
+    pub fn push(&mut self, message: impl Into<Message>) {
+        self.messages.push(message.into());
+    }
+
+    /// 追加用户消息
+    pub fn push_user(&mut self, content: impl Into<MessageContent>) {
+        self.messages.push(Message::User(param::UserMessage {
+            role: "user".into(),
+            content: content.into(),
+        }));
+    }
+
+    /// 追加工具调用结果，`tool_call_id` 对应助手消息中的工具调用
+    pub fn push_tool_result(
+        &mut self,
+        tool_call_id: impl Into<String>,
+        content: impl Into<String>,
+    ) {
+        self.messages.push(Message::Tool(param::ToolMessage {
+            role: "tool".into(),
+            content: content.into(),
+            tool_call_id: Some(tool_call_id.into()),
+        }));
+    }
+
+    /// 清空对话历史，保留系统提示词
+    pub fn clear(&mut self) {
+        self.messages.retain(|m| matches!(m, Message::System(_)));
+        self.last_reasoning = None;
+    }
+
+    /// 按上下文管理器截断会话历史，被移除的消息不再保留
+    ///
+    /// 与 [`Client::with_context_manager`] 只截断单次请求不同，该方法会直接修改会话中保存的历史，
+    /// 适合长期运行、需要持久化的会话。
+    pub async fn fit_context(
+        &mut self,
+        client: &Client,
+        context_manager: &ContextManager,
+    ) -> Result<TruncationReport> {
+        let mut request = self.request();
+        let report = context_manager.fit(client, &mut request).await?;
+        self.messages = request.input.messages;
+        Ok(report)
+    }
+
+    /// 根据当前历史构造请求
+    pub fn request(&self) -> GenerationParam {
+        GenerationParam {
+            model: self.model.clone(),
+            input: Input {
+                messages: self.messages.clone(),
+            },
+            parameters: self.parameters.clone(),
+            stream: Some(false),
+            stream_options: None,
+        }
+    }
+
+    /// 把模型回复作为助手消息追加到历史中
+    ///
+    /// 回复中包含工具调用时会一并记录，调用方执行工具后通过 [`ChatSession::push_tool_result`]
+    /// 追加结果，再调用 [`ChatSession::resume`] 继续对话。
+    pub fn record(&mut self, output: &GenerationOutput) -> Result<()> {
+        let Some(message) = output
+            .output
+            .choices
+            .as_ref()
+            .and_then(|choices| choices.first())
+            .map(|choice| &choice.message)
+        else {
+            // 非 message 格式的回复只有 text 字段
+            let text = output.output.text.clone().unwrap_or_default();
+            let (content, reasoning) = split_think(&text);
+            self.last_reasoning = reasoning;
+            self.messages
+                .push(MessageBuilder::new("assistant", content).build()?);
+            return Ok(());
+        };
+
+        let (content, inline_reasoning) = split_think(&message.content);
+        self.last_reasoning = message
+            .reasoning_content
+            .clone()
+            .filter(|r| !r.is_empty())
+            .or(inline_reasoning);
+
+        let mut builder = MessageBuilder::new("assistant", content);
+        if let Some(calls) = message.tool_calls.as_ref().filter(|c| !c.is_empty()) {
+            builder.tool_calls(calls.iter().map(param::ToolCall::from).collect());
+        }
+        self.messages.push(builder.build()?);
+        Ok(())
+    }
+
+    /// 发送用户消息并把回复追加到历史中，请求失败时撤销该用户消息
+    pub async fn send(
+        &mut self,
+        client: &Client,
+        content: impl Into<MessageContent>,
+    ) -> Result<GenerationOutput> {
+        self.send_with_options(client, content, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项发送用户消息，参见 [`ChatSession::send`]
+    pub async fn send_with_options(
+        &mut self,
+        client: &Client,
+        content: impl Into<MessageContent>,
+        options: &RequestOptions,
+    ) -> Result<GenerationOutput> {
+        self.push_user(content);
+        let result = self.resume_with_options(client, options).await;
+        if result.is_err() {
+            self.messages.pop();
+        }
+        result
+    }
+
+    /// 不追加新的用户消息，直接基于当前历史请求（例如追加工具结果之后）
+    pub async fn resume(&mut self, client: &Client) -> Result<GenerationOutput> {
+        self.resume_with_options(client, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项基于当前历史请求，参见 [`ChatSession::resume`]
+    pub async fn resume_with_options(
+        &mut self,
+        client: &Client,
+        options: &RequestOptions,
+    ) -> Result<GenerationOutput> {
+        let output = client
+            .generation()
+            .call_with_options(self.request(), options)
+            .await?;
+        self.record(&output)?;
+        Ok(output)
+    }
+
+    /// 以流式方式发送用户消息
+    ///
+    /// 返回的流中每一项都是增量内容；流正常结束后，用户消息与完整的回复才会一起追加到历史中。
+    /// 请求或流出错、或者流在结束前被丢弃时，历史保持不变。
+    pub async fn send_stream<'a>(
+        &'a mut self,
+        client: &'a Client,
+        content: impl Into<MessageContent>,
+    ) -> Result<ChatStream<'a>> {
+        self.send_stream_with_options(client, content, RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项以流式方式发送用户消息，参见 [`ChatSession::send_stream`]
+    pub async fn send_stream_with_options<'a>(
+        &'a mut self,
+        client: &'a Client,
+        content: impl Into<MessageContent>,
+        options: RequestOptions,
+    ) -> Result<ChatStream<'a>> {
+        // 用户消息只随请求发出，流正常结束后才与回复一起写入历史，
+        // 流出错或被提前丢弃时历史保持不变
+        let user = Message::User(param::UserMessage {
+            role: "user".into(),
+            content: content.into(),
+        });
+        let mut request = self.request();
+        request.input.messages.push(user.clone());
+        request.stream = Some(true);
+        let parameters = match request.parameters.as_mut() {
+            Some(parameters) => parameters,
+            None => request
+                .parameters
+                .insert(ParametersBuilder::default().build()?),
+        };
+        #[allow(deprecated)]
+        {
+            parameters.incremental_output = Some(true);
+        }
+
+        let mut stream = client
+            .generation()
+            .call_stream_with_options(request, &options)
+            .await?;
+
+        Ok(Box::pin(try_stream! {
+            let mut accumulator = StreamAccumulator::new();
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                accumulator.push(chunk.clone());
+                yield chunk;
+            }
+            let output = accumulator.finish().ok_or_else(|| {
+                DashScopeError::InvalidArgument("stream ended without any output".into())
+            })?;
+            self.messages.push(user);
+            if let Err(e) = self.record(&output) {
+                self.messages.pop();
+                Err(e)?;
+            }
+        }))
+    }
+
+    /// 序列化为 JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| DashScopeError::SerializationError(e.to_string()))
+    }
+
+    /// 从 [`ChatSession::to_json`] 的结果恢复会话
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| map_deserialization_error(e, json.as_bytes()))
+    }
+}
+
+/// 拆分 `content` 中以 `<think>...</think>` 包裹的思考过程
+fn split_think(content: &str) -> (String, Option<String>) {
+    let trimmed = content.trim_start();
+    if let Some((reasoning, answer)) = trimmed
+        .strip_prefix("<think>")
+        .and_then(|rest| rest.split_once("</think>"))
+    {
+        let reasoning = reasoning.trim();
+        return (
+            answer.trim_start().to_string(),
+            (!reasoning.is_empty()).then(|| reasoning.to_string()),
+        );
+    }
+    (content.to_string(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(json: serde_json::Value) -> GenerationOutput {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_record_and_round_trip() {
+        let mut session = ChatSession::new("qwen-plus").with_system("你是一个助手");
+        session.push_user("北京天气怎么样？");
+        session
+            .record(&output(serde_json::json!({
+                "output": {"choices": [{
+                    "finish_reason": "tool_calls",
+                    "message": {
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "index": 0,
+                            "function": {"name": "get_weather", "arguments": "{\"city\":\"北京\"}"}
+                        }]
+                    }
+                }]}
+            })))
+            .unwrap();
+        session.push_tool_result("call_1", "晴");
+        session
+            .record(&output(serde_json::json!({
+                "output": {"choices": [{
+                    "finish_reason": "stop",
+                    "message": {
+                        "role": "assistant",
+                        "content": "<think>查到了天气</think>\n北京今天晴。",
+                        "reasoning_content": ""
+                    }
+                }]}
+            })))
+            .unwrap();
+
+        assert_eq!(session.last_reasoning(), Some("查到了天气"));
+        let Some(Message::Assistant(last)) = session.messages().last() else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(last.content, "北京今天晴。");
+
+        let restored = ChatSession::from_json(&session.to_json().unwrap()).unwrap();
+        assert_eq!(restored, session);
+        assert!(matches!(restored.messages()[1], Message::User(_)));
+        assert!(matches!(restored.messages()[3], Message::Tool(_)));
+
+        let mut cleared = restored.with_system("新的提示词");
+        cleared.clear();
+        assert_eq!(cleared.messages().len(), 1);
+        let Message::System(system) = &cleared.messages()[0] else {
+            panic!("expected system message");
+        };
+        assert_eq!(system.content, "新的提示词");
+    }
+
+    #[tokio::test]
+    async fn test_send_stream_keeps_history_on_failure() {
+        let config = crate::config::ConfigBuilder::default()
+            .api_base("http://127.0.0.1:1")
+            .api_key("test")
+            .build()
+            .unwrap();
+        let client = Client::with_config(config);
+        let mut session = ChatSession::new("qwen-plus").with_system("你是一个助手");
+
+        // 流在消费前被丢弃
+        drop(session.send_stream(&client, "你好").await.unwrap());
+        assert_eq!(session.messages().len(), 1);
+
+        // 流以连接错误结束
+        let mut stream = session.send_stream(&client, "你好").await.unwrap();
+        assert!(stream.next().await.unwrap().is_err());
+        drop(stream);
+        assert_eq!(session.messages().len(), 1);
+    }
+}
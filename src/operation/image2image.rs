@@ -1,7 +1,6 @@
-use crate::{Client, error::Result};
+use crate::{Client, error::Result, operation::request::RequestOptions};
 pub use output::*;
 pub use param::*;
-use secrecy::ExposeSecret;
 
 mod output;
 mod param;
@@ -37,13 +36,23 @@ impl<'a> Image2Image<'a> {
     /// - 此方法会启用异步模式（X-DashScope-Async头）
     /// - 上传的文件会自动清理，无需手动处理
     pub async fn call(&self, request: Image2imageParam) -> Result<Image2ImageOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项提交图像转换任务，参见 [`Image2Image::call`]
+    pub async fn call_with_options(
+        &self,
+        request: Image2imageParam,
+        options: &RequestOptions,
+    ) -> Result<Image2ImageOutput> {
         // 检查参数
         // let validators = check_model_parameters(&request.model);
         // for valid in validators {
         //     valid.validate(&request)?;
         // }
         let request = request
-            .upload_file_to_oss(self.client.config().api_key().expose_secret())
+            .upload_file_to_oss(self.client)
             .await?;
 
         let mut headers = self.client.config().headers();
@@ -51,7 +60,7 @@ impl<'a> Image2Image<'a> {
 
         // 发送POST请求到生成服务，并等待结果
         self.client
-            .post_with_headers(IMAGE2IMAGE_PATH, request, headers)
+            .post_with_options(IMAGE2IMAGE_PATH, request, headers, options)
             .await
     }
 }
@@ -12,10 +12,10 @@ pub struct Image2imageParam {
 impl Image2imageParam {
     pub(crate) async fn upload_file_to_oss(
         mut self,
-        api_key: &str,
+        client: &crate::Client,
     ) -> Result<Self, crate::error::DashScopeError> {
         let oss_url =
-            oss_util::upload_file_and_get_url(api_key, &self.model, &self.input.image_url).await?;
+            oss_util::upload_file_and_get_url(client, &self.model, &self.input.image_url).await?;
 
         self.input.image_url = oss_url;
 
@@ -1,6 +1,8 @@
+pub mod accumulator;
 pub mod common;
 pub mod embeddings;
 pub mod generation;
+pub mod model;
 pub mod multi_modal_conversation;
 pub mod request;
 pub mod validate;
@@ -8,6 +10,8 @@ pub mod audio;
 pub mod image2image;
 pub mod task;
 pub mod text2image;
+pub mod tokenization;
+pub mod tool;
 pub mod file;
 #[cfg(feature = "websocket")]
 pub mod ws_client;
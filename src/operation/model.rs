@@ -0,0 +1,108 @@
+//! 模型能力表
+//!
+//! 记录常用文本模型的上下文长度、最大输入与最大输出 Token 数，供上下文管理等功能使用。
+//! 数值取自百炼模型列表，带日期后缀的快照版本（如 `qwen-plus-2025-01-25`）按主模型查询。
+//! 表中没有的模型可通过 [`ModelCapability::new`] 自行构造。
+
+/// 模型的 Token 限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapability {
+    /// 上下文长度（输入与输出之和的上限）
+    pub context_window: usize,
+    /// 最大输入 Token 数
+    pub max_input_tokens: usize,
+    /// 最大输出 Token 数
+    pub max_output_tokens: usize,
+}
+
+impl ModelCapability {
+    pub const fn new(
+        context_window: usize,
+        max_input_tokens: usize,
+        max_output_tokens: usize,
+    ) -> Self {
+        Self {
+            context_window,
+            max_input_tokens,
+            max_output_tokens,
+        }
+    }
+
+    /// 查询模型的 Token 限制，未知模型返回 `None`
+    pub fn lookup(model: &str) -> Option<Self> {
+        let model = strip_snapshot(model);
+        let capability = match model {
+            "qwen-max" => Self::new(32_768, 30_720, 8_192),
+            "qwen-max-latest" | "qwen3-max" | "qwen3-max-preview" => {
+                Self::new(262_144, 258_048, 65_536)
+            }
+            "qwen-plus" | "qwen-plus-latest" => Self::new(131_072, 129_024, 16_384),
+            "qwen-turbo" | "qwen-turbo-latest" => Self::new(1_000_000, 1_000_000, 16_384),
+            "qwen-flash" => Self::new(1_000_000, 997_952, 32_768),
+            "qwen-long" | "qwen-long-latest" => Self::new(10_000_000, 10_000_000, 8_192),
+            "qwq-plus" | "qwq-plus-latest" => Self::new(131_072, 98_304, 8_192),
+            "qwen3-coder-plus" | "qwen3-coder-flash" => Self::new(1_000_000, 997_952, 65_536),
+            "qwen3-235b-a22b" | "qwen3-32b" | "qwen3-30b-a3b" | "qwen3-14b" | "qwen3-8b" => {
+                Self::new(131_072, 129_024, 16_384)
+            }
+            "qwen3-4b" | "qwen3-1.7b" | "qwen3-0.6b" => Self::new(32_768, 30_720, 8_192),
+            "qwen2.5-72b-instruct"
+            | "qwen2.5-32b-instruct"
+            | "qwen2.5-14b-instruct"
+            | "qwen2.5-7b-instruct" => Self::new(131_072, 129_024, 8_192),
+            "qwen-mt-plus" | "qwen-mt-turbo" => Self::new(4_096, 2_048, 2_048),
+            "deepseek-r1" | "deepseek-r1-0528" => Self::new(65_536, 57_344, 16_384),
+            "deepseek-v3" | "deepseek-v3.1" | "deepseek-v3.2-exp" => {
+                Self::new(65_536, 57_344, 8_192)
+            }
+            "glm-4.5" | "glm-4.5-air" => Self::new(131_072, 98_304, 16_384),
+            "glm-4.6" => Self::new(202_752, 169_984, 32_768),
+            "Moonshot-Kimi-K2-Instruct" => Self::new(131_072, 131_072, 8_192),
+            _ => return None,
+        };
+        Some(capability)
+    }
+
+    /// 在预留 `reserved_output` 个输出 Token 后，允许的最大输入 Token 数
+    pub fn input_budget(&self, reserved_output: usize) -> usize {
+        self.max_input_tokens
+            .min(self.context_window.saturating_sub(reserved_output))
+    }
+}
+
+/// 去掉快照版本的日期后缀，例如 `qwen-plus-2025-01-25` -> `qwen-plus`
+pub(crate) fn strip_snapshot(model: &str) -> &str {
+    // 日期后缀形如 `-YYYY-MM-DD`，切分位置不在字符边界上时不可能是日期
+    let split = model.len().saturating_sub(11);
+    if let (Some(head), Some(suffix)) = (model.get(..split), model.get(split..)) {
+        let is_date = suffix.bytes().enumerate().all(|(i, b)| match i {
+            0 | 5 | 8 => b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+        if is_date && !head.is_empty() {
+            return head;
+        }
+    }
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let max = ModelCapability::lookup("qwen-max").unwrap();
+        assert_eq!(max.max_input_tokens, 30_720);
+        assert_eq!(
+            ModelCapability::lookup("qwen-plus-2025-01-25"),
+            ModelCapability::lookup("qwen-plus")
+        );
+        assert!(ModelCapability::lookup("unknown-model").is_none());
+        assert_eq!(strip_snapshot("自定义模型-v1"), "自定义模型-v1");
+        assert_eq!(strip_snapshot("-2025-01-25"), "-2025-01-25");
+
+        assert_eq!(max.input_budget(0), 30_720);
+        assert_eq!(max.input_budget(8_192), 24_576);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::operation::request::RequestOptions;
 use crate::{error::Result, operation::validate::Validator};
 use crate::{Client, error::DashScopeError, operation::validate::check_model_parameters};
 pub use output::*;
@@ -5,7 +6,6 @@ pub use param::{
     Element, InputBuilder, MessageBuilder, MultiModalConversationParam,
     MultiModalConversationParamBuilder, MultiModalConversationParamBuilderError,
 };
-use secrecy::ExposeSecret;
 
 mod output;
 mod param;
@@ -34,6 +34,16 @@ impl<'a> MultiModalConversation<'a> {
     pub async fn call(
         &self,
         request: MultiModalConversationParam,
+    ) -> Result<MultiModalConversationOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项调用多模态对话，参见 [`MultiModalConversation::call`]
+    pub async fn call_with_options(
+        &self,
+        request: MultiModalConversationParam,
+        options: &RequestOptions,
     ) -> Result<MultiModalConversationOutput> {
         // 检查请求是否为流式处理，如果是，则返回错误。
         if request.stream == Some(true) {
@@ -47,14 +57,22 @@ impl<'a> MultiModalConversation<'a> {
         for valid in validators {
             valid.validate(&request)?;
         }
+        if let Some(parameters) = &request.parameters {
+            parameters.validate()?;
+        }
 
         let request = request
-            .upload_file_to_oss(self.client.config().api_key().expose_secret())
+            .upload_file_to_oss(self.client)
             .await?;
 
         // 发起非流式多模态对话请求。
         self.client
-            .post(MULTIMODAL_CONVERSATION_PATH, request)
+            .post_with_options(
+                MULTIMODAL_CONVERSATION_PATH,
+                request,
+                self.client.config().headers(),
+                options,
+            )
             .await
     }
 
@@ -72,8 +90,18 @@ impl<'a> MultiModalConversation<'a> {
     /// 如果 `request` 参数中的 `stream` 属性为 `Some(false)`，
     /// 函数将返回一个错误，提示用户应使用非流式处理的 `call` 方法。
     pub async fn call_stream(
+        &self,
+        request: MultiModalConversationParam,
+    ) -> Result<MultiModalConversationOutputStream> {
+        self.call_stream_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项调用流式多模态对话，参见 [`MultiModalConversation::call_stream`]
+    pub async fn call_stream_with_options(
         &self,
         mut request: MultiModalConversationParam,
+        options: &RequestOptions,
     ) -> Result<MultiModalConversationOutputStream> {
         // 检查请求是否明确设置为非流式，如果是，则返回错误。
         if request.stream == Some(false) {
@@ -90,10 +118,18 @@ impl<'a> MultiModalConversation<'a> {
         for valid in validators {
             valid.validate(&request)?;
         }
+        if let Some(parameters) = &request.parameters {
+            parameters.validate()?;
+        }
 
         // 发起流式请求并返回结果流
         self.client
-            .post_stream(MULTIMODAL_CONVERSATION_PATH, request)
+            .post_stream_with_options(
+                MULTIMODAL_CONVERSATION_PATH,
+                request,
+                self.client.config().headers(),
+                options,
+            )
             .await
     }
 }
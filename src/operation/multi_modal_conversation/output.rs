@@ -22,6 +22,10 @@ pub struct Message {
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Choices {
+    /// 候选回复的序号，流式输出时按该字段归并各数据块。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+
     #[serde(rename = "finish_reason")]
     pub finish_reason: Option<String>,
 
@@ -24,7 +24,7 @@ pub struct MultiModalConversationParam {
 impl MultiModalConversationParam {
     pub(crate) async fn upload_file_to_oss(
         mut self,
-        api_key: &str,
+        client: &crate::Client,
     ) -> Result<Self, crate::error::DashScopeError> {
         for message in self.input.messages.iter_mut() {
             for content in message.contents.iter_mut() {
@@ -32,7 +32,7 @@ impl MultiModalConversationParam {
                     Element::Image(url) => {
                         if !is_valid_url(url) {
                             let oss_url =
-                                oss_util::upload_file_and_get_url(api_key, &self.model, url)
+                                oss_util::upload_file_and_get_url(client, &self.model, url)
                                     .await?;
                             *content = Element::Image(oss_url);
                         }
@@ -40,7 +40,7 @@ impl MultiModalConversationParam {
                     Element::Audio(url) => {
                         if !is_valid_url(url) {
                             let oss_url =
-                                oss_util::upload_file_and_get_url(api_key, &self.model, url)
+                                oss_util::upload_file_and_get_url(client, &self.model, url)
                                     .await?;
                             *content = Element::Audio(oss_url);
                         }
@@ -48,7 +48,7 @@ impl MultiModalConversationParam {
                     Element::Video(url) => {
                         if !is_valid_url(url) {
                             let oss_url =
-                                oss_util::upload_file_and_get_url(api_key, &self.model, url)
+                                oss_util::upload_file_and_get_url(client, &self.model, url)
                                     .await?;
                             *content = Element::Video(oss_url);
                         }
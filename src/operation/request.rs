@@ -1,3 +1,16 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use derive_builder::Builder;
+use reqwest::header::{HeaderName, HeaderValue};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{DashScopeError, Result};
 
 /// A trait for abstracting over different DashScope request parameter types.
 ///
@@ -12,3 +25,214 @@ pub trait RequestTrait {
     fn parameters(&self) -> Option<&Self::P>;
 
 }
+
+/// 单次请求的选项
+///
+/// 通过各操作的 `call_with_options` 系列方法传入，只对本次请求生效。
+///
+/// ```rust
+/// use std::time::Duration;
+/// use async_dashscope::operation::request::RequestOptionsBuilder;
+///
+/// let options = RequestOptionsBuilder::default()
+///     .timeout(Duration::from_secs(10))
+///     .workspace("ws-xxxx")
+///     .header("X-Custom", "value")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(setter(into, strip_option), default)]
+pub struct RequestOptions {
+    /// 请求超时时间。非流式请求为单次尝试的超时时间，流式请求为整个流的最长持续时间。
+    timeout: Option<Duration>,
+
+    /// 额外的请求头，会覆盖同名的默认请求头
+    #[builder(setter(custom))]
+    headers: Vec<(String, String)>,
+
+    /// 业务空间 ID，对应请求头 `X-DashScope-WorkSpace`
+    workspace: Option<String>,
+
+    /// 内容安全检测配置，对应请求头 `X-DashScope-DataInspection`
+    data_inspection: Option<String>,
+
+    /// 调用方生成的请求 ID，用于幂等和链路追踪，对应请求头 `X-Request-Id`
+    request_id: Option<String>,
+
+    /// 取消令牌，取消后请求（或流）会以 [`DashScopeError::Cancelled`] 结束
+    cancellation: Option<CancellationToken>,
+    /// 用量统计标签（例如租户），参见 [`UsageTracker`](crate::usage::UsageTracker)
+    #[builder(setter(custom))]
+    tags: Vec<String>,
+
+    /// 请求结束后由客户端填充的响应元数据，参见 [`ResponseMeta`]
+    meta: Option<ResponseMeta>,
+}
+
+impl RequestOptionsBuilder {
+    /// 添加一个额外的请求头，名称或值不合法时请求会返回 `InvalidArgument` 错误
+    pub fn header(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.headers
+            .get_or_insert_with(Vec::new)
+            .push((name.into(), value.into()));
+        self
+    }
+
+    /// 添加一个用量统计标签，可多次调用
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.tags.get_or_insert_with(Vec::new).push(tag.into());
+        self
+    }
+}
+
+impl RequestOptions {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn workspace(&self) -> Option<&str> {
+        self.workspace.as_deref()
+    }
+
+    pub fn data_inspection(&self) -> Option<&str> {
+        self.data_inspection.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    pub fn cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn meta(&self) -> Option<&ResponseMeta> {
+        self.meta.as_ref()
+    }
+
+    /// 将选项应用到请求上
+    pub(crate) fn apply(&self, request: &mut reqwest::Request) -> Result<()> {
+        let fixed = [
+            ("X-DashScope-WorkSpace", &self.workspace),
+            ("X-DashScope-DataInspection", &self.data_inspection),
+            ("X-Request-Id", &self.request_id),
+        ];
+        let fixed = fixed
+            .iter()
+            .filter_map(|(name, value)| value.as_deref().map(|value| (*name, value)));
+        let extra = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()));
+
+        let headers = request.headers_mut();
+        for (name, value) in fixed.chain(extra) {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                DashScopeError::InvalidArgument(format!("invalid header name: {name}"))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|_| {
+                DashScopeError::InvalidArgument(format!("invalid header value for {name}"))
+            })?;
+            headers.insert(name, value);
+        }
+
+        if let Some(timeout) = self.timeout {
+            *request.timeout_mut() = Some(timeout);
+        }
+        Ok(())
+    }
+}
+
+/// 由客户端在请求过程中填充的响应元数据，克隆后共享同一份数据
+///
+/// 通过 [`RequestOptionsBuilder::meta`] 传入，请求结束后即可读取。
+///
+/// ```rust,no_run
+/// # async fn run() -> async_dashscope::error::Result<()> {
+/// use async_dashscope::{
+///     Client,
+///     operation::request::{RequestOptionsBuilder, ResponseMeta},
+/// };
+/// # let request = todo!();
+///
+/// let meta = ResponseMeta::default();
+/// let options = RequestOptionsBuilder::default()
+///     .meta(meta.clone())
+///     .build()
+///     .unwrap();
+/// let client = Client::new();
+/// client.generation().call_with_options(request, &options).await?;
+/// println!("succeeded after {} attempt(s)", meta.attempts());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ResponseMeta {
+    attempts: Arc<AtomicU32>,
+}
+
+impl ResponseMeta {
+    /// 最近一次请求的尝试次数（从 1 开始），大于 1 表示经过了重试，尚未发出请求时为 0
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_attempts(&self, attempts: u32) {
+        self.attempts.store(attempts, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let options = RequestOptionsBuilder::default()
+            .timeout(Duration::from_secs(5))
+            .workspace("ws-1")
+            .header("X-Custom", "v")
+            .build()
+            .unwrap();
+        let mut request = reqwest::Request::new(
+            reqwest::Method::POST,
+            "http://localhost/".parse().unwrap(),
+        );
+        options.apply(&mut request).unwrap();
+
+        assert_eq!(request.headers()["X-DashScope-WorkSpace"], "ws-1");
+        assert_eq!(request.headers()["X-Custom"], "v");
+        assert_eq!(request.timeout(), Some(&Duration::from_secs(5)));
+
+        let options = RequestOptionsBuilder::default()
+            .header("bad header", "v")
+            .build()
+            .unwrap();
+        assert!(matches!(
+            options.apply(&mut request),
+            Err(DashScopeError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_meta_shared() {
+        let meta = ResponseMeta::default();
+        let options = RequestOptionsBuilder::default()
+            .meta(meta.clone())
+            .build()
+            .unwrap();
+        assert_eq!(meta.attempts(), 0);
+
+        options.clone().meta().unwrap().set_attempts(3);
+        assert_eq!(meta.attempts(), 3);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::error::{DashScopeError, Result};
-use crate::{Client, operation::common::TaskStatus};
+use crate::{
+    Client,
+    operation::{common::TaskStatus, request::RequestOptions},
+};
 use output::*;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -16,31 +19,45 @@ impl<'a> Task<'a> {
         Self { client }
     }
 
-    pub(crate) async fn query(&self, task_id: &str) -> Result<TaskResult> {
-        let http_client = self.client.http_client();
-        let headers = self.client.config().headers();
-        let req = http_client
-            .get(
-                self.client
-                    .config()
-                    .url(format!("{}/{}", TASK_PATH, task_id).as_str()),
-            )
-            .headers(headers)
-            .build()?;
+    /// 查询一次任务状态
+    pub async fn query(&self, task_id: &str) -> Result<TaskResult> {
+        self.query_with_options(task_id, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项查询任务状态，参见 [`Task::query`]
+    pub async fn query_with_options(
+        &self,
+        task_id: &str,
+        options: &RequestOptions,
+    ) -> Result<TaskResult> {
+        let request_maker = || async {
+            Ok(self
+                .client
+                .http_client
+                .get(
+                    self.client
+                        .config()
+                        .url(format!("{}/{}", TASK_PATH, task_id).as_str()),
+                )
+                .headers(self.client.config().headers())
+                .build()?)
+        };
 
-        let resp = http_client.execute(req).await?.bytes().await?;
+        let resp = self.client.execute_raw(request_maker, options).await?;
 
         // 检查响应是否为空
         if resp.is_empty() {
-            return Err(DashScopeError::ApiError(crate::error::ApiError {
-                message: "API returned empty response".to_string(),
-                request_id: None,
-                code: Some("EmptyResponse".to_string()),
-            }));
+            return Err(DashScopeError::ApiError(
+                crate::error::ApiError::new("API returned empty response")
+                    .with_code("EmptyResponse"),
+            ));
         }
 
-        let raw_response_str = String::from_utf8_lossy(resp.as_ref());
-        println!("Raw API response: {}", raw_response_str);
+        tracing::debug!(
+            "Raw API response: {}",
+            String::from_utf8_lossy(resp.as_ref())
+        );
 
         let resp_json = serde_json::from_slice::<TaskResult>(resp.as_ref()).map_err(|e| {
             crate::error::DashScopeError::JSONDeserialize {
@@ -78,10 +95,43 @@ impl<'a> Task<'a> {
         interval: u64,
         max_attempts: u32,
     ) -> Result<TaskResult> {
+        self.poll_task_status_with_options(
+            task_id,
+            interval,
+            max_attempts,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// 使用单次请求选项轮询任务状态，参见 [`Task::poll_task_status`]
+    ///
+    /// 选项作用于每一次查询请求；设置了取消令牌时，轮询间隔的等待也会被取消。
+    pub async fn poll_task_status_with_options(
+        &self,
+        task_id: &str,
+        interval: u64,
+        max_attempts: u32,
+        options: &RequestOptions,
+    ) -> Result<TaskResult> {
+        let wait = |secs: u64| async move {
+            match options.cancellation() {
+                Some(token) => tokio::select! {
+                    biased;
+                    _ = token.cancelled() => Err(DashScopeError::Cancelled),
+                    _ = sleep(Duration::from_secs(secs)) => Ok(()),
+                },
+                None => {
+                    sleep(Duration::from_secs(secs)).await;
+                    Ok(())
+                }
+            }
+        };
+
         for attempt in 1..=max_attempts {
             // println!("第 {} 次轮询...", attempt);
 
-            match self.query(task_id).await {
+            match self.query_with_options(task_id, options).await {
                 Ok(result) => {
                     let task_status = &result.output.task_status;
                     // println!("当前任务状态: {:?}", task_status);
@@ -99,7 +149,7 @@ impl<'a> Task<'a> {
                         TaskStatus::Pending | TaskStatus::Running => {
                             // 继续轮询
                             println!("任务仍在进行中，等待 {} 秒后继续轮询...", interval);
-                            sleep(Duration::from_secs(interval)).await;
+                            wait(interval).await?;
                         }
                         TaskStatus::Canceled | TaskStatus::Unknown => {
                             return Ok(result);
@@ -115,16 +165,16 @@ impl<'a> Task<'a> {
                         } => {
                             // JSON 反序列化错误，可能是 API 响应格式问题
                             // 继续重试，可能是临时问题
-                            sleep(Duration::from_secs(interval)).await;
+                            wait(interval).await?;
                         }
                         DashScopeError::Reqwest(_) => {
                             // 网络错误，继续重试
-                            sleep(Duration::from_secs(interval)).await;
+                            wait(interval).await?;
                         }
                         DashScopeError::ApiError(api_error) => {
                             // API 错误，检查是否是空响应错误
                             if api_error.code.as_deref() == Some("EmptyResponse") {
-                                sleep(Duration::from_secs(interval)).await;
+                                wait(interval).await?;
                             } else {
                                 // 其他 API 错误，可能是配置问题，直接返回错误
                                 return Err(e);
@@ -148,3 +198,28 @@ impl<'a> Task<'a> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::operation::request::RequestOptionsBuilder;
+
+    #[tokio::test]
+    async fn test_poll_with_cancelled_options() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = RequestOptionsBuilder::default()
+            .cancellation(token)
+            .build()
+            .unwrap();
+
+        let client = Client::new();
+        let result = client
+            .task()
+            .poll_task_status_with_options("task-1", 1, 3, &options)
+            .await;
+        assert!(matches!(result, Err(DashScopeError::Cancelled)));
+    }
+}
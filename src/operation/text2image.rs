@@ -1,4 +1,4 @@
-use crate::{Client, error::Result};
+use crate::{Client, error::Result, operation::request::RequestOptions};
 pub use output::*;
 pub use param::*;
 
@@ -17,6 +17,16 @@ impl<'a> Text2Image<'a> {
     }
 
     pub async fn call(&self, request: Text2imageParam) -> Result<Text2ImageOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项提交文生图任务，参见 [`Text2Image::call`]
+    pub async fn call_with_options(
+        &self,
+        request: Text2imageParam,
+        options: &RequestOptions,
+    ) -> Result<Text2ImageOutput> {
         // 检查参数
         // let validators = check_model_parameters(&request.model);
         // for valid in validators {
@@ -28,7 +38,7 @@ impl<'a> Text2Image<'a> {
 
         // 发送POST请求到生成服务，并等待结果
         self.client
-            .post_with_headers(TEXT2IMAGE_PATH, request, headers)
+            .post_with_options(TEXT2IMAGE_PATH, request, headers, options)
             .await
     }
 }
@@ -0,0 +1,51 @@
+use crate::operation::generation::GenerationParam;
+use crate::operation::request::RequestOptions;
+use crate::{Client, error::Result};
+pub use output::*;
+pub use param::*;
+
+mod output;
+mod param;
+
+const TOKENIZATION_PATH: &str = "/tokenizer";
+
+/// 服务端分词接口
+///
+/// 与本地的 [`Tokenizer`](crate::tokenizer::Tokenizer) 相比需要一次网络请求，但结果与计费一致。
+pub struct Tokenization<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Tokenization<'a> {
+    pub fn new(client: &'a Client) -> Self {
+        Self { client }
+    }
+
+    /// 调用分词接口，返回 Token ID 与 Token 文本
+    pub async fn call(&self, request: TokenizationParam) -> Result<TokenizationOutput> {
+        self.call_with_options(request, &RequestOptions::default())
+            .await
+    }
+
+    /// 使用单次请求选项调用分词接口，参见 [`Tokenization::call`]
+    pub async fn call_with_options(
+        &self,
+        request: TokenizationParam,
+        options: &RequestOptions,
+    ) -> Result<TokenizationOutput> {
+        self.client
+            .post_with_options(
+                TOKENIZATION_PATH,
+                request,
+                self.client.config().headers(),
+                options,
+            )
+            .await
+    }
+
+    /// 计算文本生成请求的输入 Token 数
+    pub async fn count_tokens(&self, request: &GenerationParam) -> Result<usize> {
+        let output = self.call(request.into()).await?;
+        Ok(output.input_tokens())
+    }
+}
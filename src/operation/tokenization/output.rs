@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::operation::common::Usage;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenizationOutput {
+    pub output: Output,
+
+    pub request_id: Option<String>,
+
+    pub usage: Option<Usage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Output {
+    /// 分词后的 Token ID
+    #[serde(default)]
+    pub token_ids: Vec<u32>,
+
+    /// 分词后的 Token 文本
+    #[serde(default)]
+    pub tokens: Vec<String>,
+}
+
+impl TokenizationOutput {
+    /// 输入的 Token 数，优先使用 `usage.input_tokens`
+    pub fn input_tokens(&self) -> usize {
+        self.usage
+            .as_ref()
+            .and_then(|usage| usage.input_tokens)
+            .map_or(self.output.token_ids.len(), |tokens| tokens as usize)
+    }
+}
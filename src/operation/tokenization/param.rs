@@ -0,0 +1,41 @@
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+use crate::operation::generation::{GenerationParam, param::Message};
+
+#[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
+pub struct TokenizationParam {
+    /// 模型名称，不同模型的分词结果可能不同
+    #[builder(setter(into))]
+    pub model: String,
+
+    pub input: TokenizationInput,
+}
+
+/// 待分词的内容，`messages` 与 `prompt` 二选一
+#[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq)]
+pub struct TokenizationInput {
+    /// 对话消息，计算结果包含角色等对话格式带来的 Token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    pub messages: Option<Vec<Message>>,
+
+    /// 纯文本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    #[builder(default=None)]
+    pub prompt: Option<String>,
+}
+
+impl From<&GenerationParam> for TokenizationParam {
+    fn from(value: &GenerationParam) -> Self {
+        Self {
+            model: value.model.clone(),
+            input: TokenizationInput {
+                messages: Some(value.input.messages.clone()),
+                prompt: None,
+            },
+        }
+    }
+}
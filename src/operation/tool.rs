@@ -0,0 +1,443 @@
+//! 工具调用运行时
+//!
+//! 在 [`ToolRegistry`] 中注册工具的名称、描述、参数的 JSON Schema 以及异步处理函数，
+//! 然后交给 [`Generation::run_with_tools`](crate::operation::generation::Generation::run_with_tools)：
+//! 它会发送请求、执行模型返回的工具调用、追加工具消息并再次请求，直到模型给出最终回复。
+//!
+//! ```rust,no_run
+//! # async fn run() -> async_dashscope::error::Result<()> {
+//! use async_dashscope::{Client, operation::tool::ToolRegistry};
+//! use serde_json::json;
+//! # let request = todo!();
+//!
+//! let mut tools = ToolRegistry::new();
+//! tools.register(
+//!     "get_weather",
+//!     "查询指定城市的天气",
+//!     json!({
+//!         "type": "object",
+//!         "properties": {"city": {"type": "string"}},
+//!         "required": ["city"]
+//!     }),
+//!     |args| async move {
+//!         let city = args["city"].as_str().unwrap_or_default().to_string();
+//!         Ok::<_, std::io::Error>(format!("{city}：晴，25℃"))
+//!     },
+//! )?;
+//!
+//! let client = Client::new();
+//! let result = client.generation().run_with_tools(request, &tools).await?;
+//! println!("{:?}", result.output.output.choices);
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::HashMap, fmt::Display, future::Future, pin::Pin, sync::Arc};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    error::{DashScopeError, Result},
+    operation::{
+        common::{FunctionBuilder, FunctionCall, FunctionCallBuilder, FunctionParameters},
+        generation::{GenerationOutput, ToolCall, param::Message},
+    },
+    schema::{DashScopeTool, parse_arguments},
+};
+
+/// 默认的最大请求轮数
+pub const DEFAULT_MAX_ITERATIONS: usize = 8;
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ToolError {
+    #[error("unknown tool: {0}")]
+    UnknownTool(String),
+    #[error("invalid arguments for tool {name}: {message}")]
+    InvalidArguments { name: String, message: String },
+    #[error("tool call {name} rejected: {reason}")]
+    Rejected { name: String, reason: String },
+    #[error("tool {name} failed: {message}")]
+    Execution { name: String, message: String },
+    /// 达到最大请求轮数仍未得到最终回复，`messages` 为截至此时的完整对话记录（包括工具结果）
+    #[error("no final answer after {iterations} iterations")]
+    MaxIterations {
+        iterations: usize,
+        messages: Vec<Message>,
+    },
+}
+
+/// 审批钩子的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolApproval {
+    Approve,
+    /// 拒绝执行，原因会作为工具结果返回给模型
+    Reject(String),
+}
+
+/// 工具执行失败后的处理方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolErrorAction {
+    /// 把错误信息作为工具结果返回给模型，由模型决定下一步
+    ReportToModel,
+    /// 终止整个调用循环并返回错误
+    Abort,
+}
+
+type ToolFuture = Pin<Box<dyn Future<Output = std::result::Result<String, ToolError>> + Send>>;
+type ToolHandler = Arc<dyn Fn(&str) -> ToolFuture + Send + Sync>;
+type ApprovalHook = Arc<dyn Fn(&ToolCall) -> ToolApproval + Send + Sync>;
+type ErrorHook = Arc<dyn Fn(&ToolCall, &ToolError) -> ToolErrorAction + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredTool {
+    definition: FunctionCall,
+    handler: ToolHandler,
+}
+
+/// 已注册的工具及调用循环的配置
+#[derive(Clone)]
+pub struct ToolRegistry {
+    tools: HashMap<String, RegisteredTool>,
+    /// 按注册顺序保存的工具名，保证发送给模型的工具列表顺序稳定
+    order: Vec<String>,
+    max_iterations: usize,
+    approval: Option<ApprovalHook>,
+    on_error: Option<ErrorHook>,
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.order)
+            .field("max_iterations", &self.max_iterations)
+            .finish()
+    }
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            tools: HashMap::new(),
+            order: Vec::new(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            approval: None,
+            on_error: None,
+        }
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个工具
+    ///
+    /// `parameters` 为参数的 JSON Schema，顶层必须是 `object` 类型。处理函数的返回值为字符串时原样
+    /// 作为工具结果，其他类型序列化为 JSON。同名工具会被覆盖。
+    pub fn register<F, Fut, R, E>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+        handler: F,
+    ) -> Result<&mut Self>
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<R, E>> + Send + 'static,
+        R: Serialize,
+        E: Display,
+    {
+        let name = name.into();
+        let parameters: FunctionParameters = serde_json::from_value(parameters).map_err(|e| {
+            DashScopeError::InvalidArgument(format!("invalid parameters schema for {name}: {e}"))
+        })?;
+        let definition = FunctionCallBuilder::default()
+            .typ("function")
+            .function(
+                FunctionBuilder::default()
+                    .name(name.clone())
+                    .description(description.into())
+                    .parameters(parameters)
+                    .build()
+                    .map_err(|e| DashScopeError::InvalidArgument(e.to_string()))?,
+            )
+            .build()
+            .map_err(|e| DashScopeError::InvalidArgument(e.to_string()))?;
+
+        let handler = typed_handler::<Value, _, _, _, _>(name.clone(), handler);
+        Ok(self.insert(name, definition, handler))
+    }
+
+    /// 注册一个参数类型实现了 [`DashScopeTool`] 的工具，工具定义由该类型生成
+    ///
+    /// 模型返回的 `arguments` 无法解析为 `T` 时，处理函数不会被调用，错误按
+    /// [`ToolError::InvalidArguments`] 处理。
+    pub fn register_tool<T, F, Fut, R, E>(&mut self, handler: F) -> &mut Self
+    where
+        T: DashScopeTool + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<R, E>> + Send + 'static,
+        R: Serialize,
+        E: Display,
+    {
+        let handler = typed_handler(T::NAME.to_string(), handler);
+        self.insert(T::NAME.to_string(), T::function_call(), handler)
+    }
+
+    fn insert(
+        &mut self,
+        name: String,
+        definition: FunctionCall,
+        handler: ToolHandler,
+    ) -> &mut Self {
+        if !self.tools.contains_key(&name) {
+            self.order.push(name.clone());
+        }
+        self.tools.insert(
+            name,
+            RegisteredTool {
+                definition,
+                handler,
+            },
+        );
+        self
+    }
+
+    /// 最多请求模型的轮数，默认为 [`DEFAULT_MAX_ITERATIONS`]
+    pub fn max_iterations(&mut self, max_iterations: usize) -> &mut Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// 执行每个工具调用前的审批钩子
+    pub fn with_approval<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&ToolCall) -> ToolApproval + Send + Sync + 'static,
+    {
+        self.approval = Some(Arc::new(hook));
+        self
+    }
+
+    /// 工具调用失败（包括未知工具、参数错误、被拒绝）时的钩子，默认把错误返回给模型
+    pub fn on_error<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&ToolCall, &ToolError) -> ToolErrorAction + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn get_max_iterations(&self) -> usize {
+        self.max_iterations
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// 发送给模型的工具定义
+    pub fn definitions(&self) -> Vec<FunctionCall> {
+        self.order
+            .iter()
+            .filter_map(|name| self.tools.get(name))
+            .map(|tool| tool.definition.clone())
+            .collect()
+    }
+
+    /// 执行单个工具调用，不经过审批和错误钩子
+    pub async fn call(&self, call: &ToolCall) -> std::result::Result<String, ToolError> {
+        let name = &call.function.name;
+        let tool = self
+            .tools
+            .get(name)
+            .ok_or_else(|| ToolError::UnknownTool(name.clone()))?;
+
+        (tool.handler)(call.function.arguments.as_deref().unwrap_or_default()).await
+    }
+
+    /// 经过审批与错误钩子执行工具调用，返回要发送给模型的工具结果
+    pub(crate) async fn execute(&self, call: &ToolCall) -> std::result::Result<String, ToolError> {
+        let result = match self.approval.as_ref().map(|hook| hook(call)) {
+            Some(ToolApproval::Reject(reason)) => Err(ToolError::Rejected {
+                name: call.function.name.clone(),
+                reason,
+            }),
+            _ => self.call(call).await,
+        };
+
+        match result {
+            Ok(content) => Ok(content),
+            Err(e) => {
+                tracing::warn!("tool call {} failed: {e}", call.id);
+                let action = self
+                    .on_error
+                    .as_ref()
+                    .map_or(ToolErrorAction::ReportToModel, |hook| hook(call, &e));
+                match action {
+                    ToolErrorAction::ReportToModel => Ok(format!("Error: {e}")),
+                    ToolErrorAction::Abort => Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// 包装处理函数：解析参数、执行并把返回值转换为工具结果
+fn typed_handler<T, F, Fut, R, E>(name: String, handler: F) -> ToolHandler
+where
+    T: DeserializeOwned + Send + 'static,
+    F: Fn(T) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    R: Serialize,
+    E: Display,
+{
+    Arc::new(move |arguments| {
+        let fut = parse_arguments::<T>(&name, arguments).map(&handler);
+        let name = name.clone();
+        Box::pin(async move {
+            let execution_error = |message: String| ToolError::Execution {
+                name: name.clone(),
+                message,
+            };
+            let value = fut?.await.map_err(|e| execution_error(e.to_string()))?;
+            match serde_json::to_value(value) {
+                Ok(Value::String(s)) => Ok(s),
+                Ok(value) => Ok(value.to_string()),
+                Err(e) => Err(execution_error(e.to_string())),
+            }
+        })
+    })
+}
+
+/// [`Generation::run_with_tools`](crate::operation::generation::Generation::run_with_tools) 的结果
+#[derive(Debug, Clone)]
+pub struct ToolRunOutput {
+    /// 最后一次请求的输出，即模型的最终回复
+    pub output: GenerationOutput,
+    /// 完整的对话记录，包括原始消息、模型的工具调用以及工具结果
+    pub messages: Vec<Message>,
+    /// 请求模型的次数
+    pub iterations: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::operation::generation::Function;
+
+    fn tool_call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".into(),
+            type_: "function".into(),
+            index: 0,
+            function: Function {
+                name: name.into(),
+                arguments: Some(arguments.into()),
+            },
+        }
+    }
+
+    fn registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(
+                "add",
+                "两数相加",
+                json!({"type": "object", "properties": {"a": {"type": "number"}, "b": {"type": "number"}}}),
+                |args| async move {
+                    let a = args["a"].as_f64().ok_or("missing a")?;
+                    let b = args["b"].as_f64().ok_or("missing b")?;
+                    Ok::<_, &str>(json!({"sum": a + b}))
+                },
+            )
+            .unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let registry = registry();
+        assert_eq!(registry.definitions().len(), 1);
+        assert_eq!(
+            registry
+                .call(&tool_call("add", r#"{"a": 1, "b": 2}"#))
+                .await,
+            Ok(r#"{"sum":3.0}"#.to_string())
+        );
+        assert!(matches!(
+            registry.call(&tool_call("sub", "{}")).await,
+            Err(ToolError::UnknownTool(_))
+        ));
+        assert!(matches!(
+            registry.call(&tool_call("add", "{")).await,
+            Err(ToolError::InvalidArguments { .. })
+        ));
+        assert!(matches!(
+            registry.call(&tool_call("add", "{}")).await,
+            Err(ToolError::Execution { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let mut registry = registry();
+        registry.with_approval(|call| {
+            if call.function.arguments.as_deref() == Some("{}") {
+                ToolApproval::Reject("empty arguments".into())
+            } else {
+                ToolApproval::Approve
+            }
+        });
+
+        let content = registry.execute(&tool_call("add", "{}")).await.unwrap();
+        assert!(content.starts_with("Error: ") && content.contains("empty arguments"));
+
+        registry.on_error(|_, _| ToolErrorAction::Abort);
+        assert!(matches!(
+            registry.execute(&tool_call("add", "{}")).await,
+            Err(ToolError::Rejected { .. })
+        ));
+        assert!(
+            registry
+                .execute(&tool_call("add", r#"{"a": 1, "b": 1}"#))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_tool() {
+        use async_dashscope_derive::{DashScopeTool, ToolSchema};
+        use serde::Deserialize;
+
+        /// 两数相乘
+        #[derive(Deserialize, ToolSchema, DashScopeTool)]
+        struct Multiply {
+            a: i64,
+            b: i64,
+        }
+
+        let mut registry = ToolRegistry::new();
+        registry.register_tool(|args: Multiply| async move { Ok::<_, &str>(args.a * args.b) });
+
+        assert!(registry.contains("multiply"));
+        assert_eq!(
+            registry
+                .call(&tool_call("multiply", r#"{"a": 6, "b": 7}"#))
+                .await,
+            Ok("42".to_string())
+        );
+        assert!(matches!(
+            registry.call(&tool_call("multiply", r#"{"a": 6}"#)).await,
+            Err(ToolError::InvalidArguments { .. })
+        ));
+    }
+}
@@ -2,17 +2,17 @@ use reqwest_websocket::{RequestBuilderExt, WebSocket};
 
 use crate::error::DashScopeError;
 
-const WS_URL: &str = "wss://dashscope.aliyuncs.com/api-ws/v1/inference";
-
 #[derive(Debug)]
 pub struct WsClient(pub(crate) WebSocket);
 
 impl WsClient {
     pub async fn into_ws_client(client: crate::Client) -> Result<Self, DashScopeError> {
+        let mut headers = client.config.headers();
+        client.config.authorize(&mut headers)?;
         let ws = client
             .http_client
-            .get(WS_URL)
-            .headers(client.config.headers())
+            .get(client.config.ws_url())
+            .headers(headers)
             .upgrade()
             .send()
             .await?
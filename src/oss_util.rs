@@ -1,11 +1,12 @@
 use std::{path::PathBuf, str::FromStr};
 
-use reqwest::{Client, header::HeaderMap};
 use serde::Deserialize;
 use serde_json::json;
 use tokio::{fs::File, io::AsyncReadExt};
 use url::Url;
 
+use crate::{Client, operation::request::RequestOptions};
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct UploadPolicy {
@@ -30,34 +31,21 @@ pub(crate) struct PolicyData {
 
 /// 获取文件上传凭证
 pub(crate) async fn get_upload_policy(
-    api_key: &str,
+    client: &Client,
     model_name: &str,
-) -> Result<UploadPolicy, reqwest::Error> {
-    let url = "https://dashscope.aliyuncs.com/api/v1/uploads";
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        format!("Bearer {api_key}").parse().unwrap(),
-    );
-    headers.insert("Content-Type", "application/json".parse().unwrap());
+) -> Result<UploadPolicy, crate::error::DashScopeError> {
     let params = json!({
         "action": "getPolicy",
         "model": model_name
     });
-    let response = reqwest::Client::new()
-        .get(url)
-        .headers(headers)
-        .query(&params)
-        .send()
-        .await?
-        .json::<UploadPolicy>()
-        .await?;
-    // todo: handle error
-    Ok(response)
+    client
+        .get_with_params("uploads", &params, &RequestOptions::default())
+        .await
 }
 
 /// 将文件上传到临时存储OSS
 pub(crate) async fn upload_file_to_oss(
+    client: &Client,
     policy_data: PolicyData,
     mut file: File,
     file_name:&str,
@@ -84,7 +72,8 @@ pub(crate) async fn upload_file_to_oss(
             reqwest::multipart::Part::bytes(buffer).file_name(file_name.to_string()),
         );
 
-    let response = Client::new()
+    let response = client
+        .http_client
         .post(&policy_data.upload_host)
         .multipart(form)
         .send()
@@ -100,7 +89,7 @@ pub(crate) async fn upload_file_to_oss(
 }
 
 pub(crate) async fn upload_file_and_get_url(
-    api_key: &str,
+    client: &Client,
     model_name: &str,
     file_path: &str,
 ) -> Result<String, crate::error::DashScopeError> {
@@ -132,9 +121,9 @@ pub(crate) async fn upload_file_and_get_url(
         ));
     }
 
-    let policy_data = get_upload_policy(api_key, model_name).await?;
+    let policy_data = get_upload_policy(client, model_name).await?;
 
-    let url = upload_file_to_oss(policy_data.data, file,file_name).await?;
+    let url = upload_file_to_oss(client, policy_data.data, file, file_name).await?;
 
     Ok(url)
 }
@@ -154,12 +143,12 @@ mod test {
     #[tokio::test]
     async fn test_get_upload_policy() -> Result<(), Box<dyn std::error::Error>> {
         let _ = dotenvy::dotenv();
-        let Ok(api_key) = std::env::var("DASHSCOPE_API_KEY") else {
+        if std::env::var("DASHSCOPE_API_KEY").is_err() {
             println!("DASHSCOPE_API_KEY not set, skipping test");
             return Ok(());
         };
         let model_name = "qwen-vl-max";
-        let result = get_upload_policy(&api_key, model_name).await;
+        let result = get_upload_policy(&Client::new(), model_name).await;
         assert!(result.is_ok());
 
         Ok(())
@@ -0,0 +1,334 @@
+//! 客户端限流
+//!
+//! [`RateLimiter`] 按模型维护 RPM（每分钟请求数）与 TPM（每分钟 token 数）两个令牌桶，
+//! 并可限制同时进行中的请求数量。限流器通过 [`RateLimiterBuilder`] 配置，构造完成后不能再修改；
+//! 它注册在 [`Client`](crate::Client) 上，所有克隆出的 `Client` 共享同一份配额。
+//!
+//! 请求前无法得知本次调用会消耗多少 token，因此 TPM 桶只要余额为正即可放行，
+//! 调用结束后再按响应中 `usage` 的总 token 数扣减，余额为负时后续请求会等待其恢复。
+//!
+//! ```rust
+//! use async_dashscope::{
+//!     Client,
+//!     rate_limit::{Quota, RateLimiter},
+//! };
+//!
+//! let limiter = RateLimiter::builder()
+//!     .max_in_flight(8)
+//!     .default_quota(Quota::rpm(60))
+//!     .model_quota("qwen-plus", Quota::rpm(600).with_tpm(1_000_000))
+//!     .build();
+//! let client = Client::new().with_rate_limiter(limiter);
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::operation::common::Usage;
+
+/// 单个模型的配额
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// 每分钟请求数
+    pub rpm: Option<u32>,
+    /// 每分钟 token 数
+    pub tpm: Option<u32>,
+}
+
+impl Quota {
+    pub fn rpm(rpm: u32) -> Self {
+        Self {
+            rpm: Some(rpm),
+            tpm: None,
+        }
+    }
+
+    pub fn tpm(tpm: u32) -> Self {
+        Self {
+            rpm: None,
+            tpm: Some(tpm),
+        }
+    }
+
+    pub fn with_rpm(mut self, rpm: u32) -> Self {
+        self.rpm = Some(rpm);
+        self
+    }
+
+    pub fn with_tpm(mut self, tpm: u32) -> Self {
+        self.tpm = Some(tpm);
+        self
+    }
+}
+
+/// 令牌桶，容量为每分钟配额，按秒匀速补充
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            available: per_minute as f64,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    /// 余额达到 `amount` 还需要等待的时间
+    fn wait_for(&self, amount: f64) -> Duration {
+        if self.available >= amount || self.capacity <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((amount - self.available) * 60.0 / self.capacity)
+    }
+}
+
+#[derive(Debug)]
+struct ModelBuckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    default_quota: Option<Quota>,
+    quotas: HashMap<String, Quota>,
+    buckets: Mutex<HashMap<String, ModelBuckets>>,
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+/// 客户端限流器，克隆后共享同一份状态
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+/// [`RateLimiter`] 的构造器
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiterBuilder {
+    max_in_flight: Option<usize>,
+    default_quota: Option<Quota>,
+    quotas: HashMap<String, Quota>,
+}
+
+impl RateLimiterBuilder {
+    /// 同时进行中的请求（包括流式请求）的最大数量
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// 未单独配置的模型使用的配额
+    pub fn default_quota(mut self, quota: Quota) -> Self {
+        self.default_quota = Some(quota);
+        self
+    }
+
+    /// 为指定模型设置配额
+    pub fn model_quota(mut self, model: impl Into<String>, quota: Quota) -> Self {
+        self.quotas.insert(model.into(), quota);
+        self
+    }
+
+    pub fn build(self) -> RateLimiter {
+        RateLimiter {
+            inner: Arc::new(Inner {
+                default_quota: self.default_quota,
+                quotas: self.quotas,
+                buckets: Mutex::default(),
+                semaphore: self.max_in_flight.map(|max| Arc::new(Semaphore::new(max))),
+            }),
+        }
+    }
+}
+
+/// 限流许可，持有期间占用一个并发名额
+#[derive(Debug)]
+pub struct RateLimitPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// 不做任何限制的限流器，需要配额时使用 [`RateLimiter::builder`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn builder() -> RateLimiterBuilder {
+        RateLimiterBuilder::default()
+    }
+
+    pub fn quota(&self, model: &str) -> Option<Quota> {
+        self.inner
+            .quotas
+            .get(model)
+            .copied()
+            .or(self.inner.default_quota)
+    }
+
+    /// 等待并获取一次请求的许可
+    pub async fn acquire(&self, model: Option<&str>) -> RateLimitPermit {
+        let permit = match &self.inner.semaphore {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+
+        if let Some(model) = model.filter(|model| self.quota(model).is_some()) {
+            loop {
+                let wait = self.try_take(model);
+                if wait.is_zero() {
+                    break;
+                }
+                tracing::debug!("rate limited on {model}, waiting {wait:?}");
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        RateLimitPermit { _permit: permit }
+    }
+
+    /// 尝试扣除一次请求，返回还需等待的时间，为零表示已放行
+    fn try_take(&self, model: &str) -> Duration {
+        let mut buckets = self
+            .inner
+            .buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let buckets = self.buckets_for(&mut buckets, model);
+        let now = Instant::now();
+
+        let mut wait = Duration::ZERO;
+        if let Some(tokens) = buckets.tokens.as_mut() {
+            tokens.refill(now);
+            // 只要求余额为正，实际消耗在调用结束后扣减
+            wait = wait.max(tokens.wait_for(f64::MIN_POSITIVE));
+        }
+        if let Some(requests) = buckets.requests.as_mut() {
+            requests.refill(now);
+            wait = wait.max(requests.wait_for(1.0));
+        }
+
+        if wait.is_zero() {
+            if let Some(requests) = buckets.requests.as_mut() {
+                requests.available -= 1.0;
+            }
+        }
+        wait
+    }
+
+    fn buckets_for<'a>(
+        &self,
+        buckets: &'a mut HashMap<String, ModelBuckets>,
+        model: &str,
+    ) -> &'a mut ModelBuckets {
+        let quota = self.quota(model).unwrap_or_default();
+        buckets
+            .entry(model.to_string())
+            .or_insert_with(|| ModelBuckets {
+                requests: quota.rpm.map(TokenBucket::new),
+                tokens: quota.tpm.map(TokenBucket::new),
+            })
+    }
+
+    /// 按实际消耗扣减模型的 TPM 余额
+    pub fn record_usage(&self, model: &str, usage: &Usage) {
+        self.record_tokens(model, usage.total().max(0) as u64);
+    }
+
+    pub fn record_tokens(&self, model: &str, tokens: u64) {
+        if tokens == 0 || self.quota(model).is_none_or(|q| q.tpm.is_none()) {
+            return;
+        }
+        let mut buckets = self
+            .inner
+            .buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(bucket) = self.buckets_for(&mut buckets, model).tokens.as_mut() {
+            bucket.refill(Instant::now());
+            bucket.available -= tokens as f64;
+        }
+    }
+}
+
+/// 从 JSON 请求体中提取模型名称
+pub(crate) fn request_model(request: &reqwest::Request) -> Option<String> {
+    let body = request.body()?.as_bytes()?;
+    #[derive(serde::Deserialize)]
+    struct ModelProbe {
+        model: String,
+    }
+    serde_json::from_slice::<ModelProbe>(body)
+        .ok()
+        .map(|p| p.model)
+}
+
+/// 从 JSON 响应中提取 `usage`
+pub(crate) fn response_usage(value: &serde_json::Value) -> Option<Usage> {
+    value
+        .get("usage")
+        .filter(|u| !u.is_null())
+        .and_then(|u| serde_json::from_value(u.clone()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rpm() {
+        let limiter = RateLimiter::builder()
+            .model_quota("qwen-plus", Quota::rpm(2))
+            .build();
+
+        let start = tokio::time::Instant::now();
+        limiter.acquire(Some("qwen-plus")).await;
+        limiter.acquire(Some("qwen-plus")).await;
+        // 未配置配额的模型不受限制
+        limiter.acquire(Some("qwen-max")).await;
+        limiter.acquire(None).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        assert!(limiter.try_take("qwen-plus") > Duration::from_secs(25));
+    }
+
+    #[test]
+    fn test_tpm_debit() {
+        let limiter = RateLimiter::builder()
+            .default_quota(Quota::tpm(100))
+            .build();
+        assert!(limiter.try_take("qwen-plus").is_zero());
+
+        limiter.record_tokens("qwen-plus", 160);
+        let wait = limiter.try_take("qwen-plus");
+        assert!(wait > Duration::from_secs(30) && wait <= Duration::from_secs(37));
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight() {
+        let limiter = RateLimiter::builder().max_in_flight(1).build();
+        let shared = limiter.clone();
+
+        let permit = limiter.acquire(None).await;
+        let second = tokio::time::timeout(Duration::from_millis(50), shared.acquire(None)).await;
+        assert!(second.is_err());
+
+        drop(permit);
+        let second = tokio::time::timeout(Duration::from_millis(50), shared.acquire(None)).await;
+        assert!(second.is_ok());
+    }
+}
@@ -0,0 +1,205 @@
+//! 请求重试策略
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use async_dashscope::{Client, retry::RetryPolicyBuilder};
+//!
+//! let policy = RetryPolicyBuilder::default()
+//!     .max_attempts(3u32)
+//!     .max_elapsed_time(Duration::from_secs(60))
+//!     .build()
+//!     .unwrap();
+//! let client = Client::new().with_retry_policy(policy);
+//! ```
+use std::time::Duration;
+
+use derive_builder::Builder;
+use reqwest::{StatusCode, header::HeaderMap};
+
+use crate::error::{DashScopeError, is_transient_network_error};
+
+/// 默认可重试的 HTTP 状态码
+pub const DEFAULT_RETRY_STATUS: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// 默认可重试的 DashScope 错误码，`Throttling` 同时匹配 `Throttling.RateQuota` 等子错误码
+pub const DEFAULT_RETRY_CODES: [&str; 1] = ["Throttling"];
+
+/// 请求重试策略
+///
+/// 重试间隔由 `Client` 上的 `ExponentialBackoff` 决定，该策略负责判断哪些错误可以重试，
+/// 以及重试的次数和总耗时上限。
+#[derive(Debug, Clone, Builder, PartialEq)]
+#[builder(setter(into))]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包含第一次请求），`None` 表示不限制次数
+    #[builder(setter(strip_option), default = "Some(5)")]
+    max_attempts: Option<u32>,
+
+    /// 重试的总耗时上限，`None` 表示使用 `ExponentialBackoff` 自身的配置
+    #[builder(setter(strip_option), default = "Some(Duration::from_secs(900))")]
+    max_elapsed_time: Option<Duration>,
+
+    /// 可重试的 HTTP 状态码
+    #[builder(default = "DEFAULT_RETRY_STATUS.to_vec()")]
+    retry_status: Vec<u16>,
+
+    /// 可重试的 DashScope 错误码（前缀匹配，以 `.` 分隔）
+    #[builder(default = "DEFAULT_RETRY_CODES.iter().map(|c| c.to_string()).collect()")]
+    retry_codes: Vec<String>,
+
+    /// 是否重试连接失败、连接重置、超时等网络错误
+    #[builder(default = "true")]
+    retry_network_errors: bool,
+
+    /// 是否遵循响应头中的 `Retry-After`
+    #[builder(default = "true")]
+    respect_retry_after: bool,
+
+    /// 流式请求在收到第一个事件之前失败时是否重新建立连接，默认关闭
+    #[builder(default = "false")]
+    retry_stream_connect: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicyBuilder::default().build().unwrap()
+    }
+}
+
+impl RetryPolicy {
+    /// 不进行任何重试的策略
+    pub fn none() -> Self {
+        Self {
+            max_attempts: Some(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    pub fn max_elapsed_time(&self) -> Option<Duration> {
+        self.max_elapsed_time
+    }
+
+    pub fn retry_status(&self) -> &[u16] {
+        &self.retry_status
+    }
+
+    pub fn retry_codes(&self) -> &[String] {
+        &self.retry_codes
+    }
+
+    pub fn retry_network_errors(&self) -> bool {
+        self.retry_network_errors
+    }
+
+    pub fn respect_retry_after(&self) -> bool {
+        self.respect_retry_after
+    }
+
+    pub fn retry_stream_connect(&self) -> bool {
+        self.retry_stream_connect
+    }
+
+    /// 判断 HTTP 状态码或 DashScope 错误码是否可以重试
+    pub fn is_retryable_response(&self, status: StatusCode, code: Option<&str>) -> bool {
+        if self.retry_status.contains(&status.as_u16()) {
+            return true;
+        }
+        code.is_some_and(|code| {
+            self.retry_codes.iter().any(|c| {
+                code == c
+                    || code
+                        .strip_prefix(c.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+        })
+    }
+
+    /// 判断网络错误是否可以重试
+    pub fn is_retryable_network_error(&self, error: &reqwest::Error) -> bool {
+        self.retry_network_errors && is_transient_network_error(error)
+    }
+
+    /// 判断第 `attempt` 次尝试失败后是否还能继续重试
+    pub fn has_attempts_left(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt < max)
+    }
+
+    /// 从响应头中解析 `Retry-After`，仅支持秒数形式
+    pub(crate) fn retry_after(&self, headers: &HeaderMap) -> Option<Duration> {
+        if !self.respect_retry_after {
+            return None;
+        }
+        headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// 根据是否可重试以及剩余次数，将错误转换为 backoff 可识别的错误
+    pub(crate) fn to_backoff_error(
+        &self,
+        attempt: u32,
+        err: DashScopeError,
+        retryable: bool,
+        retry_after: Option<Duration>,
+    ) -> backoff::Error<DashScopeError> {
+        if retryable && self.has_attempts_left(attempt) {
+            tracing::warn!("attempt {attempt} failed, retrying: {err}");
+            backoff::Error::Transient { err, retry_after }
+        } else {
+            backoff::Error::Permanent(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_response() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable_response(StatusCode::TOO_MANY_REQUESTS, None));
+        assert!(policy.is_retryable_response(StatusCode::BAD_GATEWAY, None));
+        assert!(!policy.is_retryable_response(StatusCode::BAD_REQUEST, None));
+        assert!(policy.is_retryable_response(StatusCode::BAD_REQUEST, Some("Throttling")));
+        assert!(policy.is_retryable_response(
+            StatusCode::BAD_REQUEST,
+            Some("Throttling.RateQuota")
+        ));
+        assert!(!policy.is_retryable_response(
+            StatusCode::BAD_REQUEST,
+            Some("ThrottlingX")
+        ));
+    }
+
+    #[test]
+    fn test_attempts_left() {
+        let policy = RetryPolicyBuilder::default()
+            .max_attempts(3u32)
+            .build()
+            .unwrap();
+        assert!(policy.has_attempts_left(2));
+        assert!(!policy.has_attempts_left(3));
+        assert!(!RetryPolicy::none().has_attempts_left(1));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let policy = RetryPolicy::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(policy.retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let policy = RetryPolicyBuilder::default()
+            .respect_retry_after(false)
+            .build()
+            .unwrap();
+        assert_eq!(policy.retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,358 @@
+//! 从 Rust 类型生成工具参数与结构化输出的 JSON Schema
+//!
+//! [`ToolSchema`] 描述一个类型对应的 JSON Schema，[`DashScopeTool`] 在此基础上补充工具名称与描述，
+//! 并负责把模型返回的 `arguments` 反序列化为该类型。开启 `derive` feature 后可以直接派生：
+//!
+//! ```rust,ignore
+//! use async_dashscope::schema::{DashScopeTool, ToolSchema};
+//! use serde::Deserialize;
+//!
+//! /// 查询指定城市的天气
+//! #[derive(Deserialize, ToolSchema, DashScopeTool)]
+//! struct GetWeather {
+//!     /// 城市名称，例如“杭州”
+//!     city: String,
+//!     /// 温度单位，默认为摄氏度
+//!     unit: Option<Unit>,
+//! }
+//!
+//! #[derive(Deserialize, ToolSchema)]
+//! #[serde(rename_all = "lowercase")]
+//! enum Unit {
+//!     Celsius,
+//!     Fahrenheit,
+//! }
+//!
+//! let definition = GetWeather::function_call();
+//! let args = GetWeather::from_arguments(r#"{"city": "杭州"}"#).unwrap();
+//! ```
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, json};
+
+use crate::operation::{
+    common::{FunctionBuilder, FunctionCall, FunctionCallBuilder, FunctionParameters},
+    tool::ToolError,
+};
+
+#[cfg(feature = "derive")]
+pub use async_dashscope_derive::{DashScopeTool, ToolSchema};
+pub use serde_json::Value;
+
+/// 可以描述为 JSON Schema 的类型
+pub trait ToolSchema {
+    fn schema() -> Value;
+
+    /// 作为结构体字段时是否可以省略，`Option<T>` 为 `true`
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+/// 可以作为工具调用的参数类型
+pub trait DashScopeTool: ToolSchema + DeserializeOwned {
+    /// 工具名称
+    const NAME: &'static str;
+    /// 工具描述
+    const DESCRIPTION: &'static str;
+
+    /// 发送给模型的工具定义
+    fn function_call() -> FunctionCall {
+        let parameters: FunctionParameters =
+            serde_json::from_value(Self::schema()).expect("tool schema must be an object schema");
+        let mut function = FunctionBuilder::default();
+        function.name(Self::NAME).parameters(parameters);
+        if !Self::DESCRIPTION.is_empty() {
+            function.description(Self::DESCRIPTION);
+        }
+        FunctionCallBuilder::default()
+            .typ("function")
+            .function(function.build().expect("name is set"))
+            .build()
+            .expect("all fields are set")
+    }
+
+    /// 将模型返回的 `arguments` 解析为该类型
+    fn from_arguments(arguments: &str) -> Result<Self, ToolError> {
+        parse_arguments(Self::NAME, arguments)
+    }
+}
+
+/// 错误信息中附带的 `arguments` 最大长度
+const ARGUMENTS_SNIPPET_LEN: usize = 200;
+
+/// 将工具调用的 `arguments` 解析为指定类型
+///
+/// 空字符串视为 `{}`；模型偶尔会用 Markdown 代码块包裹 JSON，这种情况也能正确解析。
+pub fn parse_arguments<T: DeserializeOwned>(name: &str, arguments: &str) -> Result<T, ToolError> {
+    let trimmed = strip_code_fence(arguments.trim());
+    let trimmed = if trimmed.is_empty() { "{}" } else { trimmed };
+
+    serde_json::from_str(trimmed).map_err(|e| {
+        let mut snippet: String = arguments.chars().take(ARGUMENTS_SNIPPET_LEN).collect();
+        if snippet.len() < arguments.len() {
+            snippet.push_str("...");
+        }
+        ToolError::InvalidArguments {
+            name: name.to_string(),
+            message: format!("{e}; arguments: {snippet}"),
+        }
+    })
+}
+
+/// 去掉包裹内容的 Markdown 代码块标记
+pub(crate) fn strip_code_fence(s: &str) -> &str {
+    let Some(rest) = s.strip_prefix("```") else {
+        return s;
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+    rest.strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// 构造 `object` 类型的 Schema，派生宏生成的代码使用它逐个添加字段
+#[derive(Debug, Clone, Default)]
+pub struct ObjectSchema {
+    description: Option<String>,
+    properties: Map<String, Value>,
+    required: Vec<String>,
+}
+
+impl ObjectSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn description(mut self, description: Option<&str>) -> Self {
+        self.description = description.map(str::to_string);
+        self
+    }
+
+    /// 添加一个字段，`has_default` 为 `true` 或字段类型可省略时不加入 `required`
+    pub fn field<T: ToolSchema>(
+        mut self,
+        name: &str,
+        description: Option<&str>,
+        has_default: bool,
+    ) -> Self {
+        let mut schema = T::schema();
+        if let (Some(description), Some(object)) = (description, schema.as_object_mut()) {
+            object.insert("description".into(), description.into());
+        }
+        self.properties.insert(name.to_string(), schema);
+        if !has_default && !T::is_optional() {
+            self.required.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn build(self) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": self.properties,
+            "required": self.required,
+        });
+        if let Some(description) = self.description {
+            schema["description"] = description.into();
+        }
+        schema
+    }
+}
+
+/// 字符串枚举的 Schema
+pub fn enum_schema(variants: &[&str], description: Option<&str>) -> Value {
+    let mut schema = json!({"type": "string", "enum": variants});
+    if let Some(description) = description {
+        schema["description"] = description.into();
+    }
+    schema
+}
+
+macro_rules! impl_tool_schema {
+    ($typ:literal => $($t:ty),*) => {
+        $(
+            impl ToolSchema for $t {
+                fn schema() -> Value {
+                    json!({"type": $typ})
+                }
+            }
+        )*
+    };
+}
+
+impl_tool_schema!("string" => String, str, char);
+impl_tool_schema!("boolean" => bool);
+impl_tool_schema!("integer" => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_tool_schema!("number" => f32, f64);
+
+impl ToolSchema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: ToolSchema + ?Sized> ToolSchema for &T {
+    fn schema() -> Value {
+        T::schema()
+    }
+
+    fn is_optional() -> bool {
+        T::is_optional()
+    }
+}
+
+impl<T: ToolSchema + ?Sized> ToolSchema for Box<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+
+    fn is_optional() -> bool {
+        T::is_optional()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Option<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+macro_rules! impl_array_schema {
+    ($($t:ident),*) => {
+        $(
+            impl<T: ToolSchema> ToolSchema for $t<T> {
+                fn schema() -> Value {
+                    json!({"type": "array", "items": T::schema()})
+                }
+            }
+        )*
+    };
+}
+
+impl_array_schema!(Vec, HashSet, BTreeSet);
+
+impl<T: ToolSchema> ToolSchema for [T] {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema()})
+    }
+}
+
+impl<T: ToolSchema, S> ToolSchema for HashMap<String, T, S> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_dashscope_derive::{DashScopeTool, ToolSchema};
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// 查询指定城市的天气
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolSchema, DashScopeTool)]
+    #[serde(rename_all = "camelCase")]
+    struct GetWeather {
+        /// 城市名称
+        city_name: String,
+        unit: Option<Unit>,
+        #[serde(default)]
+        days: u8,
+        #[serde(skip)]
+        cache: Vec<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolSchema, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Unit {
+        Celsius,
+        Fahrenheit,
+    }
+
+    #[test]
+    fn test_derive_schema() {
+        assert_eq!(
+            GetWeather::schema(),
+            json!({
+                "type": "object",
+                "description": "查询指定城市的天气",
+                "properties": {
+                    "cityName": {"type": "string", "description": "城市名称"},
+                    "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                    "days": {"type": "integer"},
+                },
+                "required": ["cityName"],
+            })
+        );
+
+        let definition = GetWeather::function_call();
+        let function = definition.function.unwrap();
+        assert_eq!(function.name(), "get_weather");
+        assert_eq!(function.description(), Some("查询指定城市的天气"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolSchema)]
+    #[serde(rename_all = "UPPERCASE")]
+    struct Upper {
+        city_name: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolSchema)]
+    #[serde(rename_all = "lowercase")]
+    struct Lower {
+        city_name: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, ToolSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Mode {
+        FastMode,
+        Slow,
+    }
+
+    #[test]
+    fn test_rename_all_matches_serde() {
+        // 字段名中的下划线与 serde 一样保留
+        assert_eq!(Upper::schema()["required"], json!(["CITY_NAME"]));
+        assert_eq!(Lower::schema()["required"], json!(["city_name"]));
+        assert!(serde_json::from_value::<Upper>(json!({"CITY_NAME": "杭州"})).is_ok());
+        assert!(serde_json::from_value::<Lower>(json!({"city_name": "杭州"})).is_ok());
+
+        assert_eq!(Mode::schema()["enum"], json!(["fast_mode", "slow"]));
+        assert!(serde_json::from_value::<Mode>(json!("fast_mode")).is_ok());
+    }
+
+    #[test]
+    fn test_from_arguments() {
+        let args = GetWeather::from_arguments(
+            "```json\n{\"cityName\": \"杭州\", \"unit\": \"celsius\"}\n```",
+        )
+        .unwrap();
+        assert_eq!(args.city_name, "杭州");
+        assert_eq!(args.unit, Some(Unit::Celsius));
+
+        let Err(ToolError::InvalidArguments { name, message }) =
+            GetWeather::from_arguments(r#"{"unit": "kelvin"}"#)
+        else {
+            panic!("expected invalid arguments");
+        };
+        assert_eq!(name, "get_weather");
+        assert!(message.contains("kelvin") && message.contains("arguments:"));
+    }
+}
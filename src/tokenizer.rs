@@ -0,0 +1,179 @@
+//! 本地 Token 计数
+//!
+//! 在发送请求前估算输入的 Token 数，用于预算控制与上下文截断。
+//!
+//! - 默认使用启发式估算：中日韩字符每字 1 个 Token，其余字符每 4 个字符 1 个 Token，结果通常略高于实际值；
+//! - 开启 `tokenizer` feature 后，可以加载通义千问的 BPE 词表（`qwen.tiktoken`）得到精确结果；
+//! - 需要与服务端完全一致时，可以调用 [`Tokenization`](crate::operation::tokenization::Tokenization) 接口。
+//!
+//! ```rust
+//! use async_dashscope::tokenizer::Tokenizer;
+//!
+//! // 设置了 DASHSCOPE_TOKENIZER_FILE 且开启 `tokenizer` feature 时加载 BPE 词表，否则使用启发式估算
+//! let tokenizer = Tokenizer::from_env();
+//! assert!(tokenizer.count("你好，世界") > 0);
+//! ```
+#[cfg(feature = "tokenizer")]
+use std::sync::Arc;
+
+use crate::operation::generation::{GenerationParam, param::Message};
+
+#[cfg(feature = "tokenizer")]
+mod bpe;
+
+/// BPE 词表文件路径的环境变量
+pub const DASHSCOPE_TOKENIZER_FILE: &str = "DASHSCOPE_TOKENIZER_FILE";
+
+/// 每条消息除内容外的固定开销，对应 `<|im_start|>{role}\n...<|im_end|>\n`
+const MESSAGE_OVERHEAD_TOKENS: usize = 5;
+
+/// Token 计数器
+///
+/// 克隆的开销很小，加载的词表在克隆之间共享。
+#[derive(Debug, Clone, Default)]
+pub struct Tokenizer {
+    #[cfg(feature = "tokenizer")]
+    bpe: Option<Arc<bpe::Bpe>>,
+}
+
+impl Tokenizer {
+    /// 启发式估算
+    pub fn heuristic() -> Self {
+        Self::default()
+    }
+
+    /// 从 `qwen.tiktoken` 格式的词表文件加载（每行为 base64 编码的 Token 与其序号）
+    #[cfg(feature = "tokenizer")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> crate::error::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            crate::error::DashScopeError::ConfigError(format!(
+                "failed to read tokenizer file {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_tiktoken(&data)
+    }
+
+    /// 从 `qwen.tiktoken` 格式的词表内容加载
+    #[cfg(feature = "tokenizer")]
+    pub fn from_tiktoken(data: &str) -> crate::error::Result<Self> {
+        Ok(Self {
+            bpe: Some(Arc::new(bpe::Bpe::parse(data)?)),
+        })
+    }
+
+    /// 从环境变量 `DASHSCOPE_TOKENIZER_FILE` 指定的词表加载，未设置或加载失败时使用启发式估算
+    pub fn from_env() -> Self {
+        #[cfg(feature = "tokenizer")]
+        if let Ok(path) = std::env::var(DASHSCOPE_TOKENIZER_FILE) {
+            match Self::from_file(&path) {
+                Ok(tokenizer) => return tokenizer,
+                Err(e) => tracing::warn!("falling back to heuristic token counting: {e}"),
+            }
+        }
+        Self::heuristic()
+    }
+
+    /// 是否使用 BPE 词表精确计数
+    pub fn is_exact(&self) -> bool {
+        #[cfg(feature = "tokenizer")]
+        return self.bpe.is_some();
+        #[cfg(not(feature = "tokenizer"))]
+        false
+    }
+
+    /// 把文本编码为 Token ID，未加载词表时返回 `None`
+    pub fn encode(&self, text: &str) -> Option<Vec<u32>> {
+        #[cfg(feature = "tokenizer")]
+        if let Some(bpe) = &self.bpe {
+            return Some(bpe.encode(text));
+        }
+        let _ = text;
+        None
+    }
+
+    /// 文本的 Token 数
+    pub fn count(&self, text: &str) -> usize {
+        #[cfg(feature = "tokenizer")]
+        if let Some(bpe) = &self.bpe {
+            return bpe.count(text);
+        }
+        heuristic_count(text)
+    }
+
+    /// 单条消息的 Token 数，包括角色等固定开销与工具调用
+    pub fn count_message(&self, message: &Message) -> usize {
+        let tool_calls = match message {
+            Message::None => return 0,
+            Message::Assistant(m) => m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|c| self.count(&c.function.name) + self.count(&c.function.arguments))
+                .sum(),
+            _ => 0,
+        };
+        MESSAGE_OVERHEAD_TOKENS + self.count(&message.content()) + tool_calls
+    }
+
+    /// 一组消息的 Token 数
+    pub fn count_messages(&self, messages: &[Message]) -> usize {
+        messages.iter().map(|m| self.count_message(m)).sum()
+    }
+
+    /// 请求的输入 Token 数，包括消息与工具定义
+    pub fn count_tokens(&self, request: &GenerationParam) -> usize {
+        let tools = request
+            .parameters
+            .as_ref()
+            .and_then(|p| p.tools.as_ref())
+            .and_then(|tools| serde_json::to_string(tools).ok())
+            .map_or(0, |tools| self.count(&tools));
+        self.count_messages(&request.input.messages) + tools
+    }
+}
+
+/// 使用启发式估算请求的输入 Token 数，参见 [`Tokenizer::count_tokens`]
+pub fn count_tokens(request: &GenerationParam) -> usize {
+    Tokenizer::heuristic().count_tokens(request)
+}
+
+fn heuristic_count(text: &str) -> usize {
+    let mut cjk = 0;
+    let mut other = 0_usize;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+    }
+    cjk + other.div_ceil(4)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3000..=0x303F | 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+        | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF | 0x20000..=0x2FA1F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::generation::MessageBuilder;
+
+    #[test]
+    fn test_heuristic() {
+        let tokenizer = Tokenizer::heuristic();
+        assert!(!tokenizer.is_exact());
+        assert_eq!(tokenizer.count("你好"), 2);
+        assert_eq!(tokenizer.count("hello world"), 3);
+        assert_eq!(tokenizer.count(""), 0);
+        assert!(tokenizer.encode("hello").is_none());
+
+        let message = MessageBuilder::new("user", "你好").build().unwrap();
+        assert_eq!(tokenizer.count_message(&message), 7);
+        assert_eq!(tokenizer.count_message(&Message::None), 0);
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use base64::Engine as _;
+use fancy_regex::Regex;
+
+use crate::error::{DashScopeError, Result};
+
+/// 通义千问词表的预分词规则
+const QWEN_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// 字节级 BPE 编码器
+#[derive(Debug)]
+pub(super) struct Bpe {
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl Bpe {
+    pub(super) fn parse(data: &str) -> Result<Self> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let mut ranks = HashMap::new();
+        for (line_no, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                DashScopeError::ConfigError(format!(
+                    "invalid tokenizer entry at line {}",
+                    line_no + 1
+                ))
+            };
+            let (token, rank) = line.split_once(' ').ok_or_else(invalid)?;
+            let token = engine.decode(token).map_err(|_| invalid())?;
+            let rank = rank.trim().parse::<u32>().map_err(|_| invalid())?;
+            ranks.insert(token, rank);
+        }
+        // 字节级 BPE 要求每个单字节都在词表中，否则无法编码任意文本
+        if let Some(byte) = (0..=u8::MAX).find(|b| !ranks.contains_key([*b].as_slice())) {
+            return Err(DashScopeError::ConfigError(format!(
+                "tokenizer vocabulary is missing byte 0x{byte:02x}"
+            )));
+        }
+
+        let pattern =
+            Regex::new(QWEN_PATTERN).map_err(|e| DashScopeError::ConfigError(e.to_string()))?;
+        Ok(Self { ranks, pattern })
+    }
+
+    pub(super) fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = Vec::new();
+        for piece in self.pieces(text) {
+            self.merge(piece.as_bytes(), |rank| tokens.push(rank));
+        }
+        tokens
+    }
+
+    pub(super) fn count(&self, text: &str) -> usize {
+        let mut count = 0;
+        for piece in self.pieces(text) {
+            self.merge(piece.as_bytes(), |_| count += 1);
+        }
+        count
+    }
+
+    fn pieces<'t>(&self, text: &'t str) -> impl Iterator<Item = &'t str> {
+        // 预分词规则是固定的，匹配过程不会超出回溯限制
+        self.pattern
+            .find_iter(text)
+            .filter_map(|m| m.ok())
+            .map(|m| m.as_str())
+    }
+
+    /// 反复合并序号最小的相邻片段，直到无法继续合并
+    fn merge(&self, piece: &[u8], mut emit: impl FnMut(u32)) {
+        if let Some(rank) = self.ranks.get(piece) {
+            emit(*rank);
+            return;
+        }
+
+        // 片段边界，第 i 个片段为 piece[bounds[i]..bounds[i + 1]]
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let best = (0..bounds.len().saturating_sub(2))
+                .filter_map(|i| {
+                    self.ranks
+                        .get(&piece[bounds[i]..bounds[i + 2]])
+                        .map(|rank| (*rank, i))
+                })
+                .min();
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => break,
+            }
+        }
+        for window in bounds.windows(2) {
+            emit(self.ranks[&piece[window[0]..window[1]]]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> String {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let mut data: String = (0..=u8::MAX)
+            .map(|b| format!("{} {b}\n", engine.encode([b])))
+            .collect();
+        data.push_str(&format!("{} 256\n", engine.encode("ab")));
+        data.push_str(&format!("{} 257\n", engine.encode("abc")));
+        data
+    }
+
+    #[test]
+    fn test_encode() {
+        let bpe = Bpe::parse(&vocabulary()).unwrap();
+        assert_eq!(bpe.encode("abc"), vec![257]);
+        assert_eq!(bpe.encode("ab ab"), vec![256, 32, 256]);
+        assert_eq!(bpe.count("abd"), 2);
+        assert_eq!(bpe.count(""), 0);
+
+        assert!(Bpe::parse("YQ== 0\n").is_err());
+    }
+}
@@ -0,0 +1,425 @@
+//! 用量统计与费用核算
+//!
+//! [`UsageTracker`] 注册在 [`Client`](crate::Client) 上后，会记录每次调用响应中的 `usage`，
+//! 按模型与标签（例如租户、业务线）汇总 Token 数、TTS 字符数，并根据 [`PriceTable`] 计算费用。
+//! 标签通过 [`RequestOptionsBuilder::tag`](crate::operation::request::RequestOptionsBuilder::tag)
+//! 为单次请求设置，所有克隆出的 `Client` 共享同一份统计。
+//!
+//! 流式请求中的 `usage` 是累计值，只会记录新增的部分，因此中途中断的流也会按已消耗的用量计入。
+//!
+//! ```rust
+//! use async_dashscope::{
+//!     Client,
+//!     operation::request::RequestOptionsBuilder,
+//!     usage::{Price, PriceTable, UsageTracker},
+//! };
+//!
+//! let tracker = UsageTracker::new().with_prices(
+//!     PriceTable::new().price("qwen-plus", Price::tokens(0.8, 2.0).with_reasoning(8.0)),
+//! );
+//! let client = Client::new().with_usage_tracker(tracker.clone());
+//! let options = RequestOptionsBuilder::default().tag("tenant:acme").build().unwrap();
+//!
+//! // ... client.generation().call_with_options(request, &options) ...
+//!
+//! let acme = tracker.tag("tenant:acme").unwrap_or_default();
+//! println!("acme: {} tokens, {:.4} 元", acme.total_tokens(), acme.cost);
+//! ```
+use std::{
+    collections::HashMap,
+    ops::AddAssign,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::operation::{common::Usage, model::strip_snapshot};
+
+/// 模型单价，单位为每百万 Token（或每百万字符）的价格
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    /// 输入 Token 单价
+    pub input: f64,
+    /// 输出 Token 单价
+    pub output: f64,
+    /// 思考过程 Token 单价，未设置时按输出单价计算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<f64>,
+    /// 字符单价，用于按字符计费的语音合成模型
+    #[serde(default)]
+    pub characters: f64,
+}
+
+impl Price {
+    /// 按输入、输出 Token 计费
+    pub fn tokens(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            ..Default::default()
+        }
+    }
+
+    /// 按字符计费
+    pub fn characters(characters: f64) -> Self {
+        Self {
+            characters,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_reasoning(mut self, reasoning: f64) -> Self {
+        self.reasoning = Some(reasoning);
+        self
+    }
+
+    /// 计算一组用量的费用
+    pub fn cost(&self, stats: &UsageStats) -> f64 {
+        let reasoning = stats.reasoning_tokens.min(stats.output_tokens);
+        let answer = stats.output_tokens - reasoning;
+        (stats.input_tokens as f64 * self.input
+            + answer as f64 * self.output
+            + reasoning as f64 * self.reasoning.unwrap_or(self.output)
+            + stats.characters as f64 * self.characters)
+            / 1_000_000.0
+    }
+}
+
+/// 模型价格表
+///
+/// 带日期后缀的快照版本（如 `qwen-plus-2025-01-25`）未单独配置时按主模型计价。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    prices: HashMap<String, Price>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置模型单价
+    pub fn price(mut self, model: impl Into<String>, price: Price) -> Self {
+        self.prices.insert(model.into(), price);
+        self
+    }
+
+    pub fn set_price(&mut self, model: impl Into<String>, price: Price) {
+        self.prices.insert(model.into(), price);
+    }
+
+    /// 查询模型单价
+    pub fn get(&self, model: &str) -> Option<&Price> {
+        self.prices
+            .get(model)
+            .or_else(|| self.prices.get(strip_snapshot(model)))
+    }
+}
+
+/// 汇总的用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// 请求次数
+    pub requests: u64,
+    pub input_tokens: u64,
+    /// 命中上下文缓存的输入 Token 数，已包含在 `input_tokens` 中
+    pub cached_tokens: u64,
+    /// 输出 Token 数，包含思考过程
+    pub output_tokens: u64,
+    /// 思考过程的 Token 数
+    pub reasoning_tokens: u64,
+    pub image_tokens: u64,
+    /// 音频 Token 数（输入与输出）
+    pub audio_tokens: u64,
+    pub video_tokens: u64,
+    /// 语音合成的字符数
+    pub characters: u64,
+    /// 按价格表计算的费用，未配置价格的模型为 0
+    pub cost: f64,
+}
+
+impl UsageStats {
+    /// 单次响应的用量
+    pub fn from_usage(usage: &Usage) -> Self {
+        let count = |v: Option<i32>| v.unwrap_or(0).max(0) as u64;
+        let output_details = usage.output_tokens_details.as_ref();
+        // 向量化等接口只返回 `total_tokens`，全部计为输入
+        let input_tokens = match (usage.input_tokens, usage.output_tokens) {
+            (None, None) => usage.total_tokens,
+            (input_tokens, _) => input_tokens,
+        };
+        Self {
+            requests: 1,
+            input_tokens: count(input_tokens),
+            cached_tokens: count(Some(usage.cached_tokens())),
+            output_tokens: count(usage.output_tokens),
+            reasoning_tokens: count(output_details.and_then(|d| d.reasoning_tokens)),
+            image_tokens: count(usage.image_tokens),
+            audio_tokens: count(usage.audio_tokens)
+                + count(output_details.and_then(|d| d.audio_tokens)),
+            video_tokens: count(usage.video_tokens),
+            characters: count(usage.characters),
+            cost: 0.0,
+        }
+    }
+
+    /// 输入与输出 Token 之和
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// 相对于 `previous` 新增的用量，用于累计值形式的流式 `usage`
+    fn since(&self, previous: &Self) -> Self {
+        Self {
+            requests: self.requests.saturating_sub(previous.requests),
+            input_tokens: self.input_tokens.saturating_sub(previous.input_tokens),
+            cached_tokens: self.cached_tokens.saturating_sub(previous.cached_tokens),
+            output_tokens: self.output_tokens.saturating_sub(previous.output_tokens),
+            reasoning_tokens: self
+                .reasoning_tokens
+                .saturating_sub(previous.reasoning_tokens),
+            image_tokens: self.image_tokens.saturating_sub(previous.image_tokens),
+            audio_tokens: self.audio_tokens.saturating_sub(previous.audio_tokens),
+            video_tokens: self.video_tokens.saturating_sub(previous.video_tokens),
+            characters: self.characters.saturating_sub(previous.characters),
+            cost: 0.0,
+        }
+    }
+}
+
+impl AddAssign<&UsageStats> for UsageStats {
+    fn add_assign(&mut self, rhs: &UsageStats) {
+        self.requests += rhs.requests;
+        self.input_tokens += rhs.input_tokens;
+        self.cached_tokens += rhs.cached_tokens;
+        self.output_tokens += rhs.output_tokens;
+        self.reasoning_tokens += rhs.reasoning_tokens;
+        self.image_tokens += rhs.image_tokens;
+        self.audio_tokens += rhs.audio_tokens;
+        self.video_tokens += rhs.video_tokens;
+        self.characters += rhs.characters;
+        self.cost += rhs.cost;
+    }
+}
+
+/// 按标签与模型划分的一条用量记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// 请求的标签，未设置标签的请求为 `None`
+    pub tag: Option<String>,
+    pub model: String,
+    pub stats: UsageStats,
+}
+
+/// 用量统计器
+///
+/// 克隆的开销很小，克隆之间共享同一份统计。
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    prices: PriceTable,
+    total: UsageStats,
+    models: HashMap<String, UsageStats>,
+    entries: HashMap<(Option<String>, String), UsageStats>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置价格表，只影响之后记录的用量
+    pub fn with_prices(self, prices: PriceTable) -> Self {
+        self.set_prices(prices);
+        self
+    }
+
+    pub fn set_prices(&self, prices: PriceTable) {
+        self.lock().prices = prices;
+    }
+
+    /// 记录一次调用的用量
+    pub fn record(&self, model: &str, tags: &[String], usage: &Usage) {
+        self.record_stats(model, tags, UsageStats::from_usage(usage));
+    }
+
+    /// 记录流式调用中累计 `usage` 相对于上一次的新增部分，返回本次的累计值
+    pub(crate) fn record_delta(
+        &self,
+        model: &str,
+        tags: &[String],
+        usage: &Usage,
+        previous: Option<&UsageStats>,
+    ) -> UsageStats {
+        let current = UsageStats::from_usage(usage);
+        let delta = match previous {
+            Some(previous) => current.since(previous),
+            None => current,
+        };
+        self.record_stats(model, tags, delta);
+        current
+    }
+
+    fn record_stats(&self, model: &str, tags: &[String], mut stats: UsageStats) {
+        let mut inner = self.lock();
+        stats.cost = inner.prices.get(model).map_or(0.0, |p| p.cost(&stats));
+        inner.total += &stats;
+        *inner.models.entry(model.to_string()).or_default() += &stats;
+
+        if tags.is_empty() {
+            *inner.entries.entry((None, model.to_string())).or_default() += &stats;
+        }
+        for tag in tags {
+            *inner
+                .entries
+                .entry((Some(tag.clone()), model.to_string()))
+                .or_default() += &stats;
+        }
+    }
+
+    /// 所有请求的用量
+    pub fn total(&self) -> UsageStats {
+        self.lock().total
+    }
+
+    /// 单个模型的用量
+    pub fn model(&self, model: &str) -> Option<UsageStats> {
+        self.lock().models.get(model).copied()
+    }
+
+    /// 单个标签的用量
+    pub fn tag(&self, tag: &str) -> Option<UsageStats> {
+        self.by_tag().remove(tag)
+    }
+
+    /// 按模型汇总的用量
+    pub fn by_model(&self) -> HashMap<String, UsageStats> {
+        self.lock().models.clone()
+    }
+
+    /// 按标签汇总的用量，不包含未设置标签的请求
+    ///
+    /// 带有多个标签的请求在每个标签下各计一次，因此各标签之和可能大于总用量。
+    pub fn by_tag(&self) -> HashMap<String, UsageStats> {
+        let inner = self.lock();
+        let mut by_tag = HashMap::<String, UsageStats>::new();
+        for ((tag, _), stats) in &inner.entries {
+            if let Some(tag) = tag {
+                *by_tag.entry(tag.clone()).or_default() += stats;
+            }
+        }
+        by_tag
+    }
+
+    /// 按标签与模型划分的全部记录
+    pub fn entries(&self) -> Vec<UsageEntry> {
+        self.lock()
+            .entries
+            .iter()
+            .map(|((tag, model), stats)| UsageEntry {
+                tag: tag.clone(),
+                model: model.clone(),
+                stats: *stats,
+            })
+            .collect()
+    }
+
+    /// 清空统计，保留价格表
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.total = UsageStats::default();
+        inner.models.clear();
+        inner.entries.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(json: serde_json::Value) -> Usage {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_tracker() {
+        let tracker = UsageTracker::new().with_prices(
+            PriceTable::new()
+                .price("qwen-plus", Price::tokens(1.0, 2.0).with_reasoning(10.0))
+                .price("qwen3-tts-flash", Price::characters(100.0)),
+        );
+        let acme = vec!["tenant:acme".to_string()];
+
+        tracker.record(
+            "qwen-plus-2025-01-25",
+            &acme,
+            &usage(serde_json::json!({
+                "input_tokens": 1_000_000,
+                "output_tokens": 500_000,
+                "output_tokens_details": {"reasoning_tokens": 100_000},
+                "prompt_tokens_details": {"cached_tokens": 600_000}
+            })),
+        );
+        tracker.record(
+            "qwen3-tts-flash",
+            &[],
+            &usage(serde_json::json!({"characters": 10_000})),
+        );
+
+        // 1.0 + 0.4 * 2.0 + 0.1 * 10.0
+        let stats = tracker.tag("tenant:acme").unwrap();
+        assert_eq!(stats.requests, 1);
+        assert_eq!(stats.reasoning_tokens, 100_000);
+        assert_eq!(stats.cached_tokens, 600_000);
+        assert!((stats.cost - 2.8).abs() < 1e-9);
+
+        let tts = tracker.model("qwen3-tts-flash").unwrap();
+        assert_eq!(tts.characters, 10_000);
+        assert!((tts.cost - 1.0).abs() < 1e-9);
+
+        let total = tracker.total();
+        assert_eq!(total.requests, 2);
+        assert!((total.cost - 3.8).abs() < 1e-9);
+        assert_eq!(tracker.entries().len(), 2);
+
+        // 流式响应中的累计 usage 只记录增量
+        let first = tracker.record_delta(
+            "qwen-max",
+            &[],
+            &usage(serde_json::json!({"input_tokens": 10, "output_tokens": 1})),
+            None,
+        );
+        tracker.record_delta(
+            "qwen-max",
+            &[],
+            &usage(serde_json::json!({"input_tokens": 10, "output_tokens": 5})),
+            Some(&first),
+        );
+        let max = tracker.model("qwen-max").unwrap();
+        assert_eq!(
+            (max.requests, max.input_tokens, max.output_tokens),
+            (1, 10, 5)
+        );
+
+        // 向量化接口只返回 total_tokens，计为输入
+        tracker.record(
+            "text-embedding-v4",
+            &[],
+            &usage(serde_json::json!({"total_tokens": 42})),
+        );
+        let embedding = tracker.model("text-embedding-v4").unwrap();
+        assert_eq!((embedding.input_tokens, embedding.total_tokens()), (42, 42));
+
+        tracker.reset();
+        assert_eq!(tracker.total(), UsageStats::default());
+        assert!(tracker.by_tag().is_empty());
+    }
+}